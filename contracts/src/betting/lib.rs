@@ -1,4 +1,13 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
+// ink! only calls its generated dispatch functions from the wasm32 and riscv32 entry points,
+// so a no-std check for any other target sees them as unused
+#![cfg_attr(
+    all(
+        not(feature = "std"),
+        not(any(target_arch = "wasm32", target_arch = "riscv32"))
+    ),
+    allow(dead_code)
+)]
 
 #[ink::contract]
 mod betting {
@@ -8,6 +17,53 @@ mod betting {
     use ink::prelude::{string::String, vec::Vec};
     use ink::storage::Mapping;
 
+    /// Default time (in milliseconds) a losing bettor has to appeal a reviewer's ruling
+    const DEFAULT_APPEAL_PERIOD: Timestamp = 3 * 24 * 60 * 60 * 1000;
+    /// Default time (in milliseconds) a reviewer's bond stays locked after they unregister.
+    /// It outlasts the appeal period so that rulings can still be slashed.
+    const DEFAULT_UNBONDING_PERIOD: Timestamp = 7 * 24 * 60 * 60 * 1000;
+    /// Default share of a reviewer's bond (in basis points) slashed when their ruling is overturned
+    const DEFAULT_SLASH_BASIS_POINTS: u16 = 5_000;
+    const BASIS_POINTS: u128 = 10_000;
+    /// Default time (in milliseconds) after an event is decided that bettors can commit outcomes
+    const DEFAULT_COMMIT_PERIOD: Timestamp = 24 * 60 * 60 * 1000;
+    /// Default time (in milliseconds) after the commit deadline that bettors can reveal outcomes
+    const DEFAULT_REVEAL_PERIOD: Timestamp = 24 * 60 * 60 * 1000;
+    /// Default share of bet creation fees (in basis points) going to the reviewer reward pool
+    const DEFAULT_REVIEWER_POOL_BASIS_POINTS: u16 = 5_000;
+    /// Default share of bet creation fees (in basis points) going to the bettor's referrer
+    const DEFAULT_REFERRER_BASIS_POINTS: u16 = 1_000;
+    /// Default time (in milliseconds) during which having bet against either bettor
+    /// keeps a reviewer from reviewing their bet
+    const DEFAULT_RECENT_OPPONENT_PERIOD: Timestamp = 30 * 24 * 60 * 60 * 1000;
+    /// Default time (in milliseconds) reviewers have to rule once assigned to a dispute
+    const DEFAULT_RULING_PERIOD: Timestamp = 3 * 24 * 60 * 60 * 1000;
+    /// Default number of times a dispute is reassigned before it escalates to the final
+    /// decision maker
+    const DEFAULT_MAX_REASSIGNMENTS: u8 = 2;
+    /// Default time (in milliseconds) bettors have to submit evidence once they disagree
+    const DEFAULT_EVIDENCE_PERIOD: Timestamp = 2 * 24 * 60 * 60 * 1000;
+    /// Default number of evidence entries each bettor can submit on a bet
+    const DEFAULT_MAX_EVIDENCE_PER_BETTOR: u8 = 5;
+    /// The longest URI or note (in bytes) an evidence entry can have
+    const MAX_EVIDENCE_URI_LENGTH: usize = 256;
+    /// Default number of reviewers on a jury panel
+    const DEFAULT_JURY_SIZE: u8 = 3;
+    /// The reputation score of a reviewer whose rulings have all been upheld
+    const MAX_REPUTATION_SCORE: u8 = 100;
+    /// The most outcomes a pool or a bet can have
+    const MAX_OUTCOMES: u8 = 32;
+    /// The longest label (in bytes) an outcome of a bet can have
+    const MAX_OUTCOME_LABEL_LENGTH: usize = 64;
+    /// Default smallest share of an offer (in basis points) a fill can take, unless it takes
+    /// the rest
+    const DEFAULT_MIN_FILL_BASIS_POINTS: u16 = 500;
+    /// The longest criteria for winning (in bytes) a bet or a pool can have
+    const MAX_CRITERIA_LENGTH: usize = 512;
+    /// Default time (in milliseconds) after a pool's cutoff that its backers have to ask for
+    /// it to be resolved before anyone can expire it
+    const DEFAULT_POOL_RESOLUTION_PERIOD: Timestamp = 30 * 24 * 60 * 60 * 1000;
+
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
     pub enum Error {
@@ -23,33 +79,159 @@ mod betting {
         InssufficientAmountOfTokensSent,
         /// Cannot reject bet if account id does not correspond to bettor 2's
         NotBettor2,
+        /// Only bettor 1 can cancel the bet
+        NotBettor1,
+        /// Bettor 1 cancelled the bet before it was accepted
+        BetCancelled,
+        /// The account cannot claim that much
+        InsufficientClaimableBalance,
+        /// Only the bettors or a keeper can settle the bet
+        NotBettorOrKeeper,
+        /// Caller is not the admin
+        NotAdmin,
+        /// The treasury does not hold that much
+        InsufficientTreasuryBalance,
+        /// The contract would no longer hold enough to cover what it owes
+        LiabilitiesNotCovered,
         /// The caller is not a valid party to the bet
         CallerNotValidBettor,
         InvalidStateForCallingFunction,
         AlreadyWithdrewWinnings,
+        /// The account is not a registered reviewer
+        NotRegisteredReviewer,
+        /// The account is already a registered reviewer
+        AlreadyRegisteredReviewer,
+        /// None of the registered reviewers can review the bet
+        NoEligibleReviewer,
+        /// The caller is not the reviewer assigned to the bet
+        NotAssignedReviewer,
+        /// The caller already voted on the bet, so they can no longer recuse themselves
+        CannotRecuseAfterVoting,
+        /// The reviewers still have time to rule on the bet
+        RulingDeadlineNotPassed,
+        /// Evidence can no longer be submitted for the bet
+        EvidenceDeadlinePassed,
+        /// The bettor has submitted as much evidence as they can for the bet
+        EvidenceLimitReached,
+        /// An evidence URI or note can be at most 256 bytes long
+        EvidenceUriTooLong,
+        /// Only the bettor who lost the reviewer's ruling can appeal it
+        NotLosingBettor,
+        /// The reviewer's ruling can no longer be appealed
+        AppealWindowClosed,
+        /// Winnings cannot be withdrawn while the reviewer's ruling can still be appealed
+        AppealWindowOpen,
+        /// The ruling has already been appealed
+        AlreadyAppealed,
+        /// Sending tokens from the contract failed
+        TransferFailed,
+        /// An amount, deadline or counter would not fit in its type
+        Overflow,
+        /// A reviewer cannot unregister or withdraw their bond while they have open cases
+        ReviewerHasOpenCases,
+        /// The reviewer has not unregistered, so their bond is not being unbonded
+        NotUnbonding,
+        /// The reviewer's bond is still in its unbonding period
+        BondStillLocked,
+        /// A share in basis points cannot exceed 10,000
+        InvalidBasisPoints,
+        /// Reputation scores range from 0 to 100
+        InvalidReputationScore,
+        /// A jury panel needs an odd number of reviewers so that it cannot tie on two outcomes
+        JurySizeMustBeOdd,
+        /// The reviewer has already voted on the bet
+        AlreadyVoted,
+        /// Outcomes on this bet have to be committed and then revealed
+        CommitRevealBet,
+        /// Outcomes on this bet are submitted directly rather than committed
+        NotCommitRevealBet,
+        /// The bettor has already committed to an outcome
+        AlreadyCommitted,
+        /// The bettor has already revealed their outcome
+        AlreadyRevealed,
+        /// The bettor did not commit to an outcome
+        NothingCommitted,
+        /// The revealed outcome and salt do not match the commitment
+        CommitmentMismatch,
+        /// Outcomes can no longer be committed
+        CommitPhaseClosed,
+        /// Outcomes can only be revealed once both bettors committed or the commit phase closed
+        RevealPhaseNotOpen,
+        /// Outcomes can no longer be revealed
+        RevealPhaseClosed,
+        /// The bet cannot be settled before the reveal phase closes
+        RevealPhaseNotOver,
+        /// Bettors cannot refer themselves
+        SelfReferral,
+        /// Fee tiers have to be in strictly ascending order of the stake
+        InvalidFeeTiers,
+        /// The token contract did not move the tokens, for instance for lack of an allowance
+        TokenTransferFailed,
+        /// The requested pool does not exist
+        PoolDoesNotExist,
+        /// A pool or a bet over labelled outcomes needs between 2 and 32 outcomes
+        InvalidNumberOfOutcomes,
+        /// The pool or bet has no outcome with that index
+        OutcomeDoesNotExist,
+        /// Stakes can no longer be placed on the pool
+        PoolClosed,
+        /// The pool cannot be resolved before its cutoff
+        PoolStillOpen,
+        /// The caller has no stake in the pool
+        NotBacker,
+        /// An outcome label can be at most 64 bytes long
+        OutcomeLabelTooLong,
+        /// Each bettor backs at least one outcome of a bet over labelled outcomes, none of them
+        /// twice, and no outcome is backed by both bettors
+        InvalidBacking,
+        /// The requested offer does not exist
+        OfferDoesNotExist,
+        /// Offers and fills have to stake something, and a fill can stake at most what is left
+        /// of the offer
+        InvalidOfferSize,
+        /// The unbonding period has to be longer than the appeal period, so that reviewers
+        /// cannot withdraw their bond before their rulings can no longer be appealed
+        UnbondingPeriodTooShort,
+        /// Both bettors, and every backer of a pool, have to stake something
+        InvalidStake,
+        /// The criteria for winning a bet or a pool, and when a bet's event is decided, can be at
+        /// most 512 bytes long
+        CriteriaTooLong,
+        /// A pool cannot be expired before its resolution deadline
+        PoolNotExpired,
+        /// A fill has to take at least the offer's minimum fill, or all that is left of it
+        FillTooSmall,
     }
 
     /// Different states that a bet can be in
     #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
     pub enum BetState {
         Created,
         BetAcceptedByBettor2,
         BetRefusedByBettor2,
+        Cancelled,
         Bettor1Voted,
         Bettor2Voted,
         Bettor1Wins,
         Bettor2Wins,
         BettorsDrew,
         BettorsDisagree,
-        YetToPayBettor1,
-        YetToPayBettor2,
+        ReviewerAssigned,
+        RulingAppealed,
+        DisputeEscalated,
         Concluded,
     }
 
     /// Different states that a bet's outcome can be in
     #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
-    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
     pub enum BetOutcome {
         Draw,
         Bettor1Wins,
@@ -58,12 +240,164 @@ mod betting {
         Undecideable,
     }
 
-    /// Information regarding a particular bet
+    /// Different states that an offer can be in
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum OfferState {
+        /// Takers can fill what is left of the offer
+        Open,
+        /// Takers matched the whole offer
+        Filled,
+        /// The maker cancelled what was left of the offer
+        Cancelled,
+    }
+
+    /// Different states that a pool can be in
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum PoolState {
+        /// Stakes can be placed until the cutoff
+        Open,
+        ReviewerAssigned,
+        DisputeEscalated,
+        /// The outcome has been ruled on, but the ruling may still be appealed
+        Resolved,
+        RulingAppealed,
+        /// The outcome is final and backers can claim what they are owed
+        PayingOut,
+    }
+
+    /// Different outcomes a pool can be resolved with
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum PoolOutcome {
+        /// The outcome with this index happened
+        Winner(u8),
+        /// Undecideable refunds every stake, as when nobody backed the winning outcome
+        Undecideable,
+    }
+
+    /// The errors defined by the PSP22 standard, for decoding what token contracts return
     #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
     #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    /// How the platform charges for a bet
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum FeeModel {
+        /// The same fee for every bet
+        Flat(Balance),
+        /// A share of the payer's stake, in basis points
+        Percentage(u16),
+        /// `(minimum stake, fee)` brackets in ascending order. A payer pays the fee of the
+        /// highest bracket their stake reaches, and nothing if it is below the first one.
+        Tiered(Vec<(Balance, Balance)>),
+        /// A share of the winner's winnings, in basis points, taken when the bet is settled.
+        /// Nothing is charged up front, and draws and refunds are not raked.
+        Rake(u16),
+    }
+
+    /// Who pays the fee up front: bettor 1 on creating the bet, bettor 2 on accepting it,
+    /// or each of them
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum FeePayer {
+        Bettor1,
+        Bettor2,
+        Both,
+    }
+
+    /// What a proposed bet would cost each bettor
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct FeeQuote {
+        /// What bettor 1 pays on top of their stake to create the bet
+        bettor_1_fee: Balance,
+        /// What bettor 2 pays on top of their stake to accept the bet
+        bettor_2_fee: Balance,
+        /// What is taken from bettor 1's winnings at settlement if they win
+        rake_if_bettor_1_wins: Balance,
+        /// What is taken from bettor 2's winnings at settlement if they win
+        rake_if_bettor_2_wins: Balance,
+    }
+
+    /// How a reviewer has performed so far
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ReviewerStats {
+        /// How many disputes the reviewer has been assigned to
+        cases_assigned: u32,
+        /// How many rulings went unappealed or were confirmed by the final decision maker
+        rulings_upheld: u32,
+        /// How many rulings the final decision maker overturned
+        rulings_overturned: u32,
+        /// How many times the reviewer failed to rule in time
+        missed_deadlines: u32,
+    }
+
+    /// Evidence a bettor submitted to support their claim in a dispute
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Evidence {
+        /// The bettor who submitted the evidence
+        submitted_by: AccountId,
+        /// The hash of the evidence's content, so it can be checked against the original
+        content_hash: Hash,
+        /// Where to find the evidence, or a short note about it
+        uri: String,
+        /// When the evidence was submitted (in unix timestamp, milliseconds)
+        submitted_at: Timestamp,
+    }
+
+    /// Information regarding a particular bet
+    #[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
     pub struct Bet {
-        /// How much is wagered on the event's outcome
-        amount_wagered: Balance,
+        /// How much bettor 1 stakes on the event's outcome
+        stake_bettor_1: Balance,
+        /// How much bettor 2 stakes against it. The winner takes both stakes, so the two
+        /// stakes set the odds.
+        stake_bettor_2: Balance,
+        /// The PSP22 token wagered and paid out, or `None` for the native currency
+        token: Option<AccountId>,
+        /// How much bettor 1 paid to create the bet
+        bet_creation_fee_paid: Balance,
+        /// How much bettor 2 pays on top of the wager to accept the bet
+        acceptance_fee: Balance,
+        /// The share of the winner's winnings (in basis points) taken at settlement
+        rake_basis_points: u16,
         /// Who is bettor 1?
         bettor_1: Option<AccountId>,
         /// Who is bettor 2?
@@ -72,45 +406,413 @@ mod betting {
         criteria_for_winning: String,
         /// When will the event conclude by (in unix timestamp, milliseconds)
         event_decided_by: String,
-        ///
+        /// How many labelled outcomes the bet is over, or zero for a bet on which bettor wins.
+        /// The labels are kept in `outcome_labels`.
+        number_of_outcomes: u8,
+        /// The offer the bet was filled from, whose outcome labels it shares
+        offer: Option<u32>,
+        /// The indices of the outcomes bettor 1 backs
+        outcomes_backed_by_bettor_1: Vec<u8>,
+        /// The indices of the outcomes bettor 2 backs
+        outcomes_backed_by_bettor_2: Vec<u8>,
+        /// What outcome bettor 1 claims the event had
         outcome_claimed_by_bettor_1: Option<BetOutcome>,
-        ///
+        /// What outcome bettor 2 claims the event had
         outcome_claimed_by_bettor_2: Option<BetOutcome>,
-        ///
-        reviewer: Option<AccountId>,
-        ///
+        /// Who referred bettor 1 to create the bet. They earn a share of every fee on it.
+        referrer: Option<AccountId>,
+        /// Until when bettors can commit to an outcome, if outcomes are committed and revealed
+        commit_deadline: Option<Timestamp>,
+        /// Until when bettors can reveal their committed outcome
+        reveal_deadline: Option<Timestamp>,
+        /// The hash of the outcome and salt bettor 1 committed to
+        outcome_commitment_by_bettor_1: Option<Hash>,
+        /// The hash of the outcome and salt bettor 2 committed to
+        outcome_commitment_by_bettor_2: Option<Hash>,
+        /// The reviewers assigned to settle a disagreement between the bettors:
+        /// a single reviewer, or a jury panel for large bets
+        reviewers: Vec<AccountId>,
+        /// What outcome each reviewer voted for, in the same order as `reviewers`
+        reviewer_votes: Vec<Option<BetOutcome>>,
+        /// The reviewers who recused themselves from the bet and may not be assigned to it again
+        recused_reviewers: Vec<AccountId>,
+        /// Until when the bettors can submit evidence, once they disagree
+        evidence_deadline: Option<Timestamp>,
+        /// How many evidence entries each bettor submitted. The entries themselves are kept
+        /// in `Betting::evidence`.
+        evidence_submitted_by_bettor_1: u8,
+        evidence_submitted_by_bettor_2: u8,
+        /// Until when the reviewers can rule before they can be replaced
+        ruling_deadline: Option<Timestamp>,
+        /// The reviewers replaced for missing the ruling deadline, who may not be assigned to
+        /// the bet again
+        overdue_reviewers: Vec<AccountId>,
+        /// How many times the reviewers were replaced for missing the ruling deadline
+        reassignments: u8,
+        /// What outcome the reviewer (or the majority of the jury panel) ruled the event had
         outcome_claimed_by_reviewer: Option<BetOutcome>,
-        ///
+        /// The bettor who asked for the dispute to be reviewed
+        dispute_requested_by: Option<AccountId>,
+        /// How much the bettor paid for the dispute to be reviewed
+        dispute_fee_paid: Balance,
+        /// When the reviewer ruled (in unix timestamp, milliseconds)
+        reviewer_ruled_at: Option<Timestamp>,
+        /// The bettor who appealed the reviewer's ruling
+        appellant: Option<AccountId>,
+        /// How much the appellant put up to appeal the ruling
+        appeal_bond_paid: Balance,
+        /// What outcome the final decision maker ruled the event had
+        outcome_decided_by_final_decision_maker: Option<BetOutcome>,
+        /// Where the bet is in its lifecycle
         state: BetState,
     }
 
+    /// An open offer that many takers can fill in part, each fill becoming a bet of its own
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Offer {
+        /// The terms each fill is bet on, with the stakes of the whole offer. The maker is
+        /// bettor 1.
+        terms: Bet,
+        /// How much of the maker's stake no fill has matched yet
+        remaining_stake_bettor_1: Balance,
+        /// How much takers can still stake against the offer
+        remaining_stake_bettor_2: Balance,
+        /// How much of the acceptance fee quoted when the offer was posted no fill has paid yet
+        remaining_acceptance_fee: Balance,
+        /// The smallest stake a fill can take, unless it takes the rest of the offer
+        min_fill: Balance,
+        /// How many times the offer was filled. The bets the fills created are kept in
+        /// `offer_fills`.
+        number_of_fills: u32,
+        /// Where the offer is in its lifecycle
+        state: OfferState,
+    }
+
+    /// Information regarding a parimutuel pool: any number of backers stake on its outcomes,
+    /// and the backers of the winning outcome split everything staked
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Pool {
+        /// Who created the pool. They cannot review it.
+        creator: AccountId,
+        /// How is the winning outcome decided?
+        criteria_for_winning: String,
+        /// Until when stakes can be placed (in unix timestamp, milliseconds)
+        betting_closes_at: Timestamp,
+        /// Until when the pool can be left open before anyone can expire it, refunding every
+        /// stake (in unix timestamp, milliseconds)
+        resolution_deadline: Timestamp,
+        /// The PSP22 token staked and paid out, or `None` for the native currency
+        token: Option<AccountId>,
+        /// The share of the pool (in basis points) taken when the winning outcome is paid out
+        rake_basis_points: u16,
+        /// Who is paid what is left once the winners' shares are rounded down
+        dust_recipient: AccountId,
+        /// How much is staked on each outcome
+        stakes_per_outcome: Vec<Balance>,
+        /// The sum of `stakes_per_outcome`
+        total_staked: Balance,
+        /// The reviewers assigned to rule on the outcome: a single reviewer, or a jury panel
+        /// for large pools
+        reviewers: Vec<AccountId>,
+        /// What outcome each reviewer voted for, in the same order as `reviewers`
+        reviewer_votes: Vec<Option<PoolOutcome>>,
+        /// Until when the reviewers can rule before they can be replaced
+        ruling_deadline: Option<Timestamp>,
+        /// The reviewers replaced for missing the ruling deadline, who may not be assigned to
+        /// the pool again
+        overdue_reviewers: Vec<AccountId>,
+        /// How many times the reviewers were replaced for missing the ruling deadline
+        reassignments: u8,
+        /// The backer who asked for the pool to be resolved
+        resolution_requested_by: Option<AccountId>,
+        /// How much the backer paid for the reviewers to rule
+        dispute_fee_paid: Balance,
+        /// What outcome the reviewer (or the majority of the jury panel) ruled
+        outcome_ruled_by_reviewers: Option<PoolOutcome>,
+        /// When the reviewers ruled (in unix timestamp, milliseconds)
+        reviewer_ruled_at: Option<Timestamp>,
+        /// The backer who appealed the reviewers' ruling
+        appellant: Option<AccountId>,
+        /// How much the appellant put up to appeal the ruling
+        appeal_bond_paid: Balance,
+        /// What outcome the final decision maker ruled
+        outcome_decided_by_final_decision_maker: Option<PoolOutcome>,
+        /// The stake the payout is split over: the winning outcome's, or every stake if they
+        /// are refunded
+        paying_stake: Balance,
+        /// What is split among the backers once the outcome is final
+        payout: Balance,
+        /// The part of `paying_stake` whose share has not been claimed yet
+        unclaimed_stake: Balance,
+        /// The part of `payout` that has not been claimed yet
+        unpaid_payout: Balance,
+        /// Where the pool is in its lifecycle
+        state: PoolState,
+    }
+
+    /// What the contract owes compared to what it holds
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub struct SolvencyReport {
+        /// Everything the contract holds on others' behalf
+        liabilities: Balance,
+        /// Fees the platform has kept
+        treasury: Balance,
+        /// What the contract holds beyond its liabilities and the treasury
+        free_balance: Balance,
+        /// Whether the contract holds enough to cover its liabilities
+        is_solvent: bool,
+    }
+
+    /// Emitted when the admin withdraws fees from the treasury
+    #[ink(event)]
+    pub struct FeesWithdrawn {
+        /// Who received the fees
+        #[ink(topic)]
+        to: AccountId,
+        /// The PSP22 token withdrawn, or `None` for the native currency
+        token: Option<AccountId>,
+        /// How much was withdrawn
+        amount: Balance,
+        /// What is left in the treasury
+        treasury_balance: Balance,
+    }
+
+    /// A backer of a pool, as (pool, backer)
+    type PoolBacker = (u32, AccountId);
+
     #[ink(storage)]
     pub struct Betting {
-        /// The amount bettor_1 pays the smart contract to create a bet
-        bet_creation_fee: Balance,
-        /// A set of registered reviewers
-        reviewers: Mapping<AccountId, ()>,
+        /// How new bets are charged
+        fee_model: FeeModel,
+        /// Which bettors pay the fee up front
+        fee_payer: FeePayer,
+        /// The share of each fee (in basis points) going to the reviewer reward pool
+        reviewer_pool_basis_points: u16,
+        /// The share of each fee (in basis points) going to the bet's referrer.
+        /// Without a referrer, it goes to the treasury along with the rest of the fee.
+        referrer_basis_points: u16,
+        /// How much the reviewers resolving a dispute are paid from the reviewer reward pool
+        dispute_reward: Balance,
+        /// Bet creation fees set aside to pay reviewers
+        reviewer_reward_pool: Balance,
+        /// Bet creation fees and forfeited appeal bonds kept by the platform
+        treasury_balance: Balance,
+        /// Bet creation fees earned by each referrer and not yet withdrawn
+        referral_rewards: Mapping<AccountId, Balance>,
+        /// The sum of `referral_rewards`
+        total_referral_rewards: Balance,
+        /// What each account is owed and can claim: winnings, refunds, rewards and overpayments
+        claimable: Mapping<AccountId, Balance>,
+        /// The sum of `claimable`
+        total_claimable: Balance,
+        /// The wagers, dispute fees and appeal bonds held for bets that are not settled yet
+        total_escrowed: Balance,
+        /// The wagers held in each PSP22 token for bets that are not settled yet
+        token_escrowed: Mapping<AccountId, Balance>,
+        /// What each account can claim in each PSP22 token, by (token, account)
+        token_claimable: Mapping<(AccountId, AccountId), Balance>,
+        /// The sum of `token_claimable` for each PSP22 token
+        token_total_claimable: Mapping<AccountId, Balance>,
+        /// Fees kept by the platform in each PSP22 token
+        token_treasury: Mapping<AccountId, Balance>,
+        /// The share of the bet creation fee (in basis points) refunded to bettor 1 when
+        /// bettor 2 rejects the bet: 0 keeps the fee, 10,000 refunds all of it
+        rejection_refund_basis_points: u16,
+        /// Registered reviewers by index, from 0 to `number_of_reviewers - 1`
+        reviewers: Mapping<u32, AccountId>,
+        /// The index of each registered reviewer in `reviewers`
+        reviewer_indices: Mapping<AccountId, u32>,
         /// How many registered reviewers there are
         number_of_reviewers: u32,
+        /// The amount a reviewer has to lock to register
+        reviewer_bond: Balance,
+        /// The share of a reviewer's bond (in basis points) slashed when their ruling is overturned
+        slash_basis_points: u16,
+        /// How long (in milliseconds) a reviewer's bond stays locked after they unregister
+        unbonding_period: Timestamp,
+        /// How much each reviewer has locked
+        reviewer_bonds: Mapping<AccountId, Balance>,
+        /// The sum of `reviewer_bonds`
+        total_reviewer_bonds: Balance,
+        /// When each unregistered reviewer can withdraw their bond
+        unbonding_until: Mapping<AccountId, Timestamp>,
+        /// How many cases each reviewer still has to rule on or that are under appeal
+        open_cases: Mapping<AccountId, u32>,
+        /// How each reviewer has performed so far
+        reviewer_stats: Mapping<AccountId, ReviewerStats>,
+        /// Reviewers with a lower reputation score are not assigned to disputes
+        min_reputation_score: u8,
+        /// How long (in milliseconds) reviewers have to rule once assigned to a dispute
+        ruling_period: Timestamp,
+        /// How many times a dispute is reassigned before it escalates to the final decision maker
+        max_reassignments: u8,
+        /// How long (in milliseconds) bettors have to submit evidence once they disagree
+        evidence_period: Timestamp,
+        /// How many evidence entries each bettor can submit on a bet
+        max_evidence_per_bettor: u8,
+        /// The evidence submitted on each bet, keyed by `(bet, index)` in the order it was
+        /// submitted
+        evidence: Mapping<(u32, u32), Evidence>,
+        /// When each pair of accounts last bet against each other, keyed by `(lower, higher)`
+        last_bet_between: Mapping<(AccountId, AccountId), Timestamp>,
+        /// How long (in milliseconds) having bet against either bettor keeps a reviewer from
+        /// reviewing their bet. Zero turns the rule off.
+        recent_opponent_period: Timestamp,
+        /// Whether the bet's referrer is kept from reviewing it
+        exclude_referrer: bool,
         /// The accountId that has final say should a reviewer's decision be appealed
         final_decision_maker: AccountId,
+        /// The accountId that can withdraw fees from the treasury
+        admin: AccountId,
+        /// Accounts allowed to settle bets on the bettors' behalf
+        keepers: Mapping<AccountId, bool>,
+        /// How long (in milliseconds) a reviewer's ruling can be appealed for
+        appeal_period: Timestamp,
+        /// The amount a bettor pays to appeal a reviewer's ruling
+        appeal_bond: Balance,
+        /// How long (in milliseconds) after an event is decided that bettors can commit outcomes
+        commit_period: Timestamp,
+        /// How long (in milliseconds) after the commit deadline that bettors can reveal outcomes
+        reveal_period: Timestamp,
+        /// The amount a bettor pays to have a dispute reviewed, shared by the reviewers who
+        /// vote with the majority
+        dispute_fee: Balance,
+        /// Disputes over bets where either bettor stakes at least this much are judged by a
+        /// jury panel
+        jury_threshold: Balance,
+        /// How many reviewers sit on a jury panel
+        jury_size: u8,
         /// Number of bets that have been made
         latest_bet: u32,
-        /// A vector of bet information
-        bets: Vec<Bet>,
+        /// The bets made, fill sub-positions included, keyed by their number
+        bets: Mapping<u32, Bet>,
+        /// The label of each outcome of a bet over labelled outcomes, keyed by
+        /// `(bet, outcome)`
+        outcome_labels: Mapping<(u32, u8), String>,
+        /// Number of offers that have been posted
+        number_of_offers: u32,
+        /// The offers posted, keyed by their number
+        offers: Mapping<u32, Offer>,
+        /// The bet each fill of an offer created, keyed by `(offer, fill)` in the order the
+        /// offer was filled
+        offer_fills: Mapping<(u32, u32), u32>,
+        /// The smallest share of new offers (in basis points) a fill can take, unless it takes
+        /// the rest
+        min_fill_basis_points: u16,
+        /// The label of each outcome of an offer over labelled outcomes, keyed by
+        /// `(offer, outcome)`. The bets filled from the offer share them.
+        offer_outcome_labels: Mapping<(u32, u8), String>,
+        /// The share of new pools (in basis points) taken when they pay out
+        pool_rake_basis_points: u16,
+        /// Who is paid the rounding dust of new pools
+        pool_dust_recipient: AccountId,
+        /// The amount paid to create a pool
+        pool_creation_fee: Balance,
+        /// How long (in milliseconds) after the cutoff a new pool can be left unresolved
+        pool_resolution_period: Timestamp,
+        /// Number of pools that have been created
+        number_of_pools: u32,
+        /// The pools created, keyed by their number
+        pools: Mapping<u32, Pool>,
+        /// What each backer staked on each outcome of a pool
+        pool_stakes: Mapping<PoolBacker, Vec<Balance>>,
+        /// How the contract reaches PSP22 token contracts
+        token_transport: psp22::Transport,
         salt: u128,
     }
 
     impl Betting {
         #[ink(constructor)]
         pub fn new(final_decision_maker: AccountId, bet_creation_fee: Balance) -> Self {
-            Self {
+            Self::with_token_transport(
+                final_decision_maker,
                 bet_creation_fee,
+                psp22::Transport::CrossContractCall,
+            )
+        }
+
+        /// A contract that reaches PSP22 token contracts through `token_transport`
+        fn with_token_transport(
+            final_decision_maker: AccountId,
+            bet_creation_fee: Balance,
+            token_transport: psp22::Transport,
+        ) -> Self {
+            Self {
+                fee_model: FeeModel::Flat(bet_creation_fee),
+                fee_payer: FeePayer::Bettor1,
+                reviewer_pool_basis_points: DEFAULT_REVIEWER_POOL_BASIS_POINTS,
+                referrer_basis_points: DEFAULT_REFERRER_BASIS_POINTS,
+                dispute_reward: Self::basis_points_of(
+                    bet_creation_fee,
+                    DEFAULT_REVIEWER_POOL_BASIS_POINTS,
+                ),
+                reviewer_reward_pool: 0,
+                treasury_balance: 0,
+                referral_rewards: Mapping::default(),
+                total_referral_rewards: 0,
+                claimable: Mapping::default(),
+                total_claimable: 0,
+                total_escrowed: 0,
+                token_escrowed: Mapping::default(),
+                token_claimable: Mapping::default(),
+                token_total_claimable: Mapping::default(),
+                token_treasury: Mapping::default(),
+                rejection_refund_basis_points: 0,
                 reviewers: Mapping::default(),
+                reviewer_indices: Mapping::default(),
                 number_of_reviewers: 0,
+                reviewer_bond: bet_creation_fee,
+                slash_basis_points: DEFAULT_SLASH_BASIS_POINTS,
+                unbonding_period: DEFAULT_UNBONDING_PERIOD,
+                reviewer_bonds: Mapping::default(),
+                total_reviewer_bonds: 0,
+                unbonding_until: Mapping::default(),
+                open_cases: Mapping::default(),
+                reviewer_stats: Mapping::default(),
+                min_reputation_score: 0,
+                ruling_period: DEFAULT_RULING_PERIOD,
+                max_reassignments: DEFAULT_MAX_REASSIGNMENTS,
+                evidence_period: DEFAULT_EVIDENCE_PERIOD,
+                max_evidence_per_bettor: DEFAULT_MAX_EVIDENCE_PER_BETTOR,
+                evidence: Mapping::default(),
+                last_bet_between: Mapping::default(),
+                recent_opponent_period: DEFAULT_RECENT_OPPONENT_PERIOD,
+                exclude_referrer: true,
                 final_decision_maker,
+                admin: final_decision_maker,
+                keepers: Mapping::default(),
+                appeal_period: DEFAULT_APPEAL_PERIOD,
+                appeal_bond: bet_creation_fee,
+                commit_period: DEFAULT_COMMIT_PERIOD,
+                reveal_period: DEFAULT_REVEAL_PERIOD,
+                dispute_fee: 0,
+                jury_threshold: Balance::MAX,
+                jury_size: DEFAULT_JURY_SIZE,
                 latest_bet: 0,
-                bets: Vec::default(),
+                bets: Mapping::default(),
+                outcome_labels: Mapping::default(),
+                number_of_offers: 0,
+                offers: Mapping::default(),
+                offer_fills: Mapping::default(),
+                min_fill_basis_points: DEFAULT_MIN_FILL_BASIS_POINTS,
+                offer_outcome_labels: Mapping::default(),
+                pool_rake_basis_points: 0,
+                pool_dust_recipient: final_decision_maker,
+                pool_creation_fee: bet_creation_fee,
+                pool_resolution_period: DEFAULT_POOL_RESOLUTION_PERIOD,
+                number_of_pools: 0,
+                pools: Mapping::default(),
+                pool_stakes: Mapping::default(),
+                token_transport,
                 salt: u128::default(),
             }
         }
@@ -120,17 +822,114 @@ mod betting {
         // --------------------------------------------------------
         /// A pseudo-random number generator made to go from 0 to `max_value`.
         /// Taken from https://docs.astar.network/docs/build/builder-guides/xvm_wasm/pseudo_random/
+        ///   Reading it does not advance the seed reviewers are selected with.
         #[ink(message)]
-        pub fn get_pseudo_random(&mut self, max_value: u8) -> u8 {
+        pub fn get_pseudo_random(&self, max_value: u8) -> u8 {
+            let output = self.pseudo_random_hash()[0];
+            // every byte is already in range when `max_value` is 255
+            match max_value.checked_add(1) {
+                Some(modulus) => output % modulus,
+                None => output,
+            }
+        }
+
+        /// A pseudo-random index from 0 to `len - 1`, for picking from a list of `len` items.
+        fn pseudo_random_index(&mut self, len: u32) -> u32 {
+            let output = self.pseudo_random_hash();
+            self.salt = self.salt.wrapping_add(1);
+            u32::from_be_bytes([output[0], output[1], output[2], output[3]]) % len
+        }
+
+        fn only_final_decision_maker(&self) -> Result<(), Error> {
+            if self.env().caller() != self.final_decision_maker {
+                return Err(Error::NotFinalDecisionMaker);
+            }
+            Ok(())
+        }
+
+        /// The deadline `period` milliseconds from now
+        fn deadline_after(&self, period: Timestamp) -> Result<Timestamp, Error> {
+            self.env()
+                .block_timestamp()
+                .checked_add(period)
+                .ok_or(Error::Overflow)
+        }
+
+        /// `amount * basis_points / BASIS_POINTS`, rounded down, without overflowing
+        /// for any `amount` as long as `basis_points` is at most 10,000
+        fn basis_points_of(amount: Balance, basis_points: u16) -> Balance {
+            let basis_points = basis_points as u128;
+            amount / BASIS_POINTS * basis_points
+                + amount % BASIS_POINTS * basis_points / BASIS_POINTS
+        }
+
+        /// `amount * numerator / denominator`, rounded down, for an `amount` of at most
+        /// `denominator` so that the result fits in a balance.
+        ///   The product is worked out in 256 bits, so it cannot overflow.
+        fn pro_rata(amount: Balance, numerator: Balance, denominator: Balance) -> Balance {
+            const LOW: u128 = u64::MAX as u128;
+            let (amount_high, amount_low) = (amount >> 64, amount & LOW);
+            let (numerator_high, numerator_low) = (numerator >> 64, numerator & LOW);
+            let low_low = amount_low * numerator_low;
+            let low_high = amount_low * numerator_high;
+            let high_low = amount_high * numerator_low;
+            let middle = (low_low >> 64) + (low_high & LOW) + (high_low & LOW);
+            let low = (low_low & LOW) | (middle << 64);
+            let high =
+                amount_high * numerator_high + (low_high >> 64) + (high_low >> 64) + (middle >> 64);
+
+            // long division of the 256-bit product, one bit at a time
+            let mut quotient = 0;
+            let mut remainder: u128 = 0;
+            for bit in (0..256).rev() {
+                let next = if bit >= 128 {
+                    (high >> (bit - 128)) & 1
+                } else {
+                    (low >> bit) & 1
+                };
+                let carry = remainder >> 127;
+                remainder = (remainder << 1) | next;
+                if carry == 1 || remainder >= denominator {
+                    remainder = remainder.wrapping_sub(denominator);
+                    if bit < 128 {
+                        quotient |= 1 << bit;
+                    }
+                }
+            }
+            quotient
+        }
+
+        /// What the winner of a bet is paid: both bettors' stakes
+        fn pot(stake_bettor_1: Balance, stake_bettor_2: Balance) -> Result<Balance, Error> {
+            stake_bettor_1
+                .checked_add(stake_bettor_2)
+                .ok_or(Error::Overflow)
+        }
+
+        fn pseudo_random_hash(&self) -> <hash::Keccak256 as hash::HashOutput>::Type {
             let seed = self.env().block_timestamp();
             let mut input: Vec<u8> = Vec::new();
             input.extend_from_slice(&seed.to_be_bytes());
             input.extend_from_slice(&self.salt.to_be_bytes());
             let mut output = <hash::Keccak256 as hash::HashOutput>::Type::default();
             ink::env::hash_bytes::<hash::Keccak256>(&input, &mut output);
-            self.salt += 1;
-            let number = output[0] % (max_value + 1);
-            number
+            output
+        }
+
+        /// The commitment `bettor` submits to `commit_outcome` on bet `n` for `winner`
+        /// (see `submit_outcome`), hidden by a secret `salt`.
+        ///   It is bound to the bet and the bettor, so a copied commitment cannot be revealed.
+        #[ink(message)]
+        pub fn get_outcome_commitment(
+            &self,
+            n: u32,
+            bettor: AccountId,
+            winner: u8,
+            salt: [u8; 32],
+        ) -> Hash {
+            let mut output = <hash::Keccak256 as hash::HashOutput>::Type::default();
+            ink::env::hash_encoded::<hash::Keccak256, _>(&(n, bettor, winner, salt), &mut output);
+            Hash::from(output)
         }
 
         /// Get contract balance
@@ -139,661 +938,7558 @@ mod betting {
             Ok(self.env().balance())
         }
 
-        /// Get bet creation fee
+        /// Get exactly what a bet with these stakes would cost each bettor under the current
+        /// fee model, including what the winner would give up to the rake
         #[ink(message)]
-        pub fn get_bet_creation_fee(&self) -> Result<Balance, ()> {
-            Ok(self.bet_creation_fee)
+        pub fn quote_fee(
+            &self,
+            stake_bettor_1: Balance,
+            stake_bettor_2: Balance,
+        ) -> Result<FeeQuote, Error> {
+            // the winner is paid both stakes, so the pot has to fit in a balance too
+            Self::pot(stake_bettor_1, stake_bettor_2)?;
+            let (bettor_1_pays, bettor_2_pays) = match self.fee_payer {
+                FeePayer::Bettor1 => (true, false),
+                FeePayer::Bettor2 => (false, true),
+                FeePayer::Both => (true, true),
+            };
+            let fee_for = |pays: bool, stake: Balance| match &self.fee_model {
+                _ if !pays => 0,
+                FeeModel::Flat(fee) => *fee,
+                FeeModel::Percentage(basis_points) => Self::basis_points_of(stake, *basis_points),
+                FeeModel::Tiered(tiers) => tiers
+                    .iter()
+                    .rev()
+                    .find(|(min_stake, _)| stake >= *min_stake)
+                    .map_or(0, |(_, fee)| *fee),
+                FeeModel::Rake(_) => 0,
+            };
+            // each side wins the other's stake
+            let rake_basis_points = self.rake_basis_points();
+            Ok(FeeQuote {
+                bettor_1_fee: fee_for(bettor_1_pays, stake_bettor_1),
+                bettor_2_fee: fee_for(bettor_2_pays, stake_bettor_2),
+                rake_if_bettor_1_wins: Self::basis_points_of(stake_bettor_2, rake_basis_points),
+                rake_if_bettor_2_wins: Self::basis_points_of(stake_bettor_1, rake_basis_points),
+            })
         }
 
-        /// Get current block timestamp
-        #[ink(message)]
-        pub fn get_current_block_timestamp(&self) -> Result<u64, Error> {
-            Ok(self.env().block_timestamp())
+        /// The share of the winnings (in basis points) the current fee model rakes
+        fn rake_basis_points(&self) -> u16 {
+            match self.fee_model {
+                FeeModel::Rake(basis_points) => basis_points,
+                _ => 0,
+            }
         }
 
-        /// Convert datetime to milliseconds since Unix epoch
+        /// (For the final decision maker): Set how new bets are charged and who pays up front.
+        ///   Bets already created keep the fees they were created with.
         #[ink(message)]
-        pub fn convert_datetime_to_ms(&self, dt: String) -> Result<i64, Error> {
-            match dt.as_str().parse::<DateTime<Utc>>() {
-                Ok(y) => Ok(y.timestamp_millis()),
-                Err(_) => Err(Error::NotDatetimeString),
+        pub fn set_fee_model(
+            &mut self,
+            fee_model: FeeModel,
+            fee_payer: FeePayer,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            match &fee_model {
+                FeeModel::Percentage(basis_points) | FeeModel::Rake(basis_points)
+                    if *basis_points as u128 > BASIS_POINTS =>
+                {
+                    return Err(Error::InvalidBasisPoints);
+                }
+                FeeModel::Tiered(tiers) if tiers.windows(2).any(|pair| pair[0].0 >= pair[1].0) => {
+                    return Err(Error::InvalidFeeTiers);
+                }
+                _ => {}
             }
+            self.fee_model = fee_model;
+            self.fee_payer = fee_payer;
+            self.debug_assert_solvent();
+            Ok(())
         }
 
-        /// Check if current datetime is past specified datetime
+        /// Get how new bets are charged and who pays up front
         #[ink(message)]
-        pub fn has_past_datetime(&self, dt: String) -> Result<bool, Error> {
-            let now = self.env().block_timestamp();
-            match dt.as_str().parse::<DateTime<Utc>>() {
-                Ok(y) => Ok(now as i128 > y.timestamp_millis() as i128),
-                Err(_) => Err(Error::NotDatetimeString),
-            }
+        pub fn get_fee_model(&self) -> (FeeModel, FeePayer) {
+            (self.fee_model.clone(), self.fee_payer)
         }
 
-        // --------------------------------------------------------
-        // Bet-related functions
-        // --------------------------------------------------------
-        #[ink(message, payable)]
-        pub fn create_bet(
-            &mut self,
-            amount_to_wager: Balance,
-            bettor_2: Option<AccountId>,
-            criteria_for_winning: String,
-            event_decided_by: String,
-        ) -> Result<Option<u32>, Error> {
-            if self.env().transferred_value() < self.bet_creation_fee + amount_to_wager {
-                return Err(Error::InssufficientAmountOfTokensSent);
+        /// Get how much of the bet creation fees is set aside to pay reviewers
+        #[ink(message)]
+        pub fn get_reviewer_reward_pool(&self) -> Balance {
+            self.reviewer_reward_pool
+        }
+
+        /// Get how much of the bet creation fees the platform has kept
+        #[ink(message)]
+        pub fn get_treasury_balance(&self) -> Balance {
+            self.treasury_balance
+        }
+
+        /// (For the admin): Withdraw `amount` of the fees kept in the treasury to `to`.
+        ///   The contract always keeps enough to cover escrowed wagers and everything it owes.
+        #[ink(message)]
+        pub fn withdraw_fees(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            self.only_admin()?;
+            if amount > self.treasury_balance {
+                return Err(Error::InsufficientTreasuryBalance);
             }
-            if event_decided_by.as_str().parse::<DateTime<Utc>>().is_err() {
-                return Err(Error::NotDatetimeString);
+            let required = self
+                .total_liabilities()?
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            if self.env().balance() < required {
+                return Err(Error::LiabilitiesNotCovered);
             }
 
-            let bet = Bet {
-                amount_wagered: amount_to_wager,
-                bettor_1: Some(self.env().caller()),
-                bettor_2,
-                criteria_for_winning,
-                event_decided_by,
-                state: BetState::Created,
-                outcome_claimed_by_bettor_1: None,
-                outcome_claimed_by_bettor_2: None,
-                reviewer: None,
-                outcome_claimed_by_reviewer: None,
-            };
-            // update latest bet number
-            let bet_number = self.latest_bet;
-            self.latest_bet = self.latest_bet + 1;
+            self.treasury_balance -= amount;
+            if self.env().transfer(to, amount).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(FeesWithdrawn {
+                to,
+                token: None,
+                amount,
+                treasury_balance: self.treasury_balance,
+            });
+            self.debug_assert_solvent();
+            Ok(())
+        }
 
-            self.bets.push(bet);
-            Ok(Some(bet_number))
+        /// (For the admin): Hand the admin role over to another account
+        #[ink(message)]
+        pub fn set_admin(&mut self, admin: AccountId) -> Result<(), Error> {
+            self.only_admin()?;
+            self.admin = admin;
+            self.debug_assert_solvent();
+            Ok(())
         }
 
-        #[ink(message, payable)]
-        pub fn reject_bet(&mut self, n: u32) -> Result<bool, Error> {
-            let caller = self.env().caller();
+        /// Get the account that can withdraw fees
+        #[ink(message)]
+        pub fn get_admin(&self) -> AccountId {
+            self.admin
+        }
 
-            match self.bets.get_mut(n as usize) {
-                Some(x) => match x.bettor_2 {
-                    Some(bettor) => {
-                        if bettor == caller {
-                            x.state = BetState::BetRefusedByBettor2;
-                        } else {
-                            return Err(Error::NotBettor2);
-                        }
-                        Ok(true)
-                    }
-                    None => Err(Error::NotBettor2),
-                },
-                None => Err(Error::BetDoesNotExist),
+        fn only_admin(&self) -> Result<(), Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::NotAdmin);
             }
+            Ok(())
         }
 
-        #[ink(message, payable)]
-        pub fn get_amount_transferred(&mut self) -> Result<Balance, ()> {
-            Ok(self.env().transferred_value())
+        /// Everything the contract holds on others' behalf: escrowed wagers, dispute fees and
+        /// appeal bonds, claimable balances, referral rewards, reviewer bonds and the reviewer
+        /// reward pool
+        fn total_liabilities(&self) -> Result<Balance, Error> {
+            [
+                self.total_claimable,
+                self.total_referral_rewards,
+                self.total_reviewer_bonds,
+                self.reviewer_reward_pool,
+            ]
+            .iter()
+            .try_fold(self.total_escrowed, |total, x| total.checked_add(*x))
+            .ok_or(Error::Overflow)
         }
 
-        #[ink(message, payable)]
-        pub fn accept_bet(&mut self, n: u32) -> Result<bool, Error> {
-            let caller = self.env().caller();
-            let transferred_amount = self.env().transferred_value();
-
-            match self.bets.get_mut(n as usize) {
-                Some(x) => {
-                    // make sure bettor2 candidate sent enough tokens
-                    if transferred_amount < x.amount_wagered {
-                        return Err(Error::InssufficientAmountOfTokensSent);
-                    }
-
-                    // allow caller to accept bet if either
-                    //   1. bettor2 has not been assigned by bettor1 or
-                    //   2. bettor2 has been assigned by bettor1 and is caller
-                    match x.bettor_2 {
-                        Some(bettor) => {
-                            if bettor == caller {
-                                x.state = BetState::BetAcceptedByBettor2;
-                            } else {
-                                return Err(Error::NotBettor2);
-                            }
-                            Ok(true)
-                        }
-                        None => {
-                            x.state = BetState::BetAcceptedByBettor2;
-                            x.bettor_2 = Some(caller);
-                            Ok(true)
-                        }
-                    }
-                }
-                None => Err(Error::BetDoesNotExist),
+        /// Get what the contract owes, what the treasury holds, what is left over, and whether
+        /// the contract holds enough to cover what it owes
+        #[ink(message)]
+        pub fn solvency_report(&self) -> SolvencyReport {
+            let balance = self.env().balance();
+            // liabilities too large to add up can never be covered
+            let liabilities = self.total_liabilities().unwrap_or(Balance::MAX);
+            SolvencyReport {
+                liabilities,
+                treasury: self.treasury_balance,
+                free_balance: balance
+                    .saturating_sub(liabilities.saturating_add(self.treasury_balance)),
+                is_solvent: balance >= liabilities,
             }
         }
 
-        /// (For bettors): Submit event's outcome.
-        ///   winner = 0 (draw), 1 (bettor1 wins), or 2 (bettor2 wins)
-        #[ink(message, payable)]
-        pub fn submit_outcome(&mut self, n: u32, winner: u8) -> Result<(), Error> {
-            let caller = self.env().caller();
-            let bet;
-            match self.bets.get_mut(n as usize) {
-                Some(y) => bet = y,
-                None => {
-                    return Err(Error::BetDoesNotExist);
-                }
+        /// Get what the contract owes in a PSP22 token, what the token's treasury holds, what is
+        /// left over, and whether the contract holds enough of the token to cover what it owes.
+        ///   A token that cannot be asked for the contract's balance counts as holding nothing.
+        #[ink(message)]
+        pub fn token_solvency_report(&self, token: AccountId) -> SolvencyReport {
+            let balance = psp22::balance_of(self.token_transport, token, self.env().account_id())
+                .unwrap_or_default();
+            // liabilities too large to add up can never be covered
+            let liabilities = self
+                .token_escrowed
+                .get(token)
+                .unwrap_or_default()
+                .checked_add(self.token_total_claimable.get(token).unwrap_or_default())
+                .unwrap_or(Balance::MAX);
+            let treasury = self.get_token_treasury_balance(token);
+            SolvencyReport {
+                liabilities,
+                treasury,
+                free_balance: balance.saturating_sub(liabilities.saturating_add(treasury)),
+                is_solvent: balance >= liabilities,
             }
+        }
 
-            // figure out what state `winner` corresponds to
-            let outcome = match winner {
-                0 => BetOutcome::Draw,
-                1 => BetOutcome::Bettor1Wins,
-                2 => BetOutcome::Bettor2Wins,
-                _ => BetOutcome::Undecideable,
-            };
+        /// Check, in debug builds, that the escrow is accounted for and that the contract
+        /// still covers its liabilities. Called by every message that moves value or changes
+        /// what the contract owes.
+        fn debug_assert_solvent(&self) {
+            if !cfg!(debug_assertions) {
+                return;
+            }
+            let bets: Vec<Bet> = (0..self.latest_bet)
+                .filter_map(|n| self.bets.get(n))
+                .collect();
+            let native_escrow = bets.iter().map(|bet| {
+                let wagers = match bet.token {
+                    None => Self::wagers_escrowed_in(bet),
+                    Some(_) => 0,
+                };
+                wagers.saturating_add(Self::fees_escrowed_in(bet))
+            });
+            let pools: Vec<Pool> = (0..self.number_of_pools)
+                .filter_map(|n| self.pools.get(n))
+                .collect();
+            let native_pool_escrow = pools.iter().map(|pool| {
+                let stakes = match pool.token {
+                    None => Self::stakes_escrowed_in(pool),
+                    Some(_) => 0,
+                };
+                stakes.saturating_add(Self::pool_fees_escrowed_in(pool))
+            });
+            let offers: Vec<Offer> = (0..self.number_of_offers)
+                .filter_map(|n| self.offers.get(n))
+                .collect();
+            let native_offer_escrow = offers
+                .iter()
+                .filter(|offer| offer.terms.token.is_none())
+                .map(Self::stake_escrowed_in_offer);
+            debug_assert_eq!(
+                self.total_escrowed,
+                native_escrow
+                    .chain(native_pool_escrow)
+                    .chain(native_offer_escrow)
+                    .sum::<Balance>()
+            );
+            // each token is checked once, however many bets, pools and offers use it
+            let mut tokens: Vec<AccountId> = bets
+                .iter()
+                .map(|bet| bet.token)
+                .chain(pools.iter().map(|pool| pool.token))
+                .chain(offers.iter().map(|offer| offer.terms.token))
+                .flatten()
+                .collect();
+            tokens.sort_unstable();
+            tokens.dedup();
+            for token in tokens {
+                let token_escrow = bets
+                    .iter()
+                    .filter(|bet| bet.token == Some(token))
+                    .map(Self::wagers_escrowed_in);
+                let token_pool_escrow = pools
+                    .iter()
+                    .filter(|pool| pool.token == Some(token))
+                    .map(Self::stakes_escrowed_in);
+                let token_offer_escrow = offers
+                    .iter()
+                    .filter(|offer| offer.terms.token == Some(token))
+                    .map(Self::stake_escrowed_in_offer);
+                let token_escrow = token_escrow
+                    .chain(token_pool_escrow)
+                    .chain(token_offer_escrow);
+                debug_assert_eq!(
+                    self.token_escrowed.get(token).unwrap_or_default(),
+                    token_escrow.sum::<Balance>()
+                );
+                debug_assert!(self.token_solvency_report(token).is_solvent);
+            }
+            debug_assert!(self.solvency_report().is_solvent);
+        }
 
-            // check if caller is bettor 1
-            if bet.bettor_1.unwrap() == caller {
-                // update state
-                if bet.state == BetState::Bettor2Voted {
-                    bet.state = match (outcome, bet.outcome_claimed_by_bettor_2.unwrap()) {
-                        (BetOutcome::Draw, BetOutcome::Draw) => BetState::BettorsDrew,
-                        (BetOutcome::Undecideable, BetOutcome::Undecideable) => {
-                            BetState::BettorsDrew
-                        }
-                        (BetOutcome::Bettor1Wins, BetOutcome::Bettor1Wins) => BetState::Bettor1Wins,
-                        (BetOutcome::Bettor2Wins, BetOutcome::Bettor2Wins) => BetState::Bettor2Wins,
-                        _ => BetState::BettorsDisagree,
-                    }
-                } else if bet.state == BetState::BetAcceptedByBettor2 {
-                    bet.outcome_claimed_by_bettor_1 = Some(outcome);
-                    bet.state = BetState::Bettor1Voted;
-                } else {
-                    return Err(Error::InvalidStateForCallingFunction);
-                }
+        /// The wagers the contract holds for a bet until it is settled, in the bet's currency.
+        ///   Each amount was checked when it was escrowed, so these fit in a balance.
+        fn wagers_escrowed_in(bet: &Bet) -> Balance {
+            match bet.state {
+                BetState::Created | BetState::BetRefusedByBettor2 => bet.stake_bettor_1,
+                BetState::Cancelled | BetState::Concluded => 0,
+                _ => bet.stake_bettor_1.saturating_add(bet.stake_bettor_2),
+            }
+        }
 
-                return Ok(());
+        /// The dispute fee or appeal bond the contract holds for a bet, in the native currency
+        fn fees_escrowed_in(bet: &Bet) -> Balance {
+            match bet.state {
+                BetState::ReviewerAssigned | BetState::DisputeEscalated => bet.dispute_fee_paid,
+                BetState::RulingAppealed => bet.appeal_bond_paid,
+                _ => 0,
             }
+        }
 
-            // check if caller is bettor 2
-            match bet.bettor_2 {
-                Some(bettor_2) if bettor_2 == caller => {
-                    // update state
-                    if bet.state == BetState::Bettor1Voted {
-                        bet.state = match (bet.outcome_claimed_by_bettor_1.unwrap(), outcome) {
-                            (BetOutcome::Draw, BetOutcome::Draw) => BetState::BettorsDrew,
-                            (BetOutcome::Undecideable, BetOutcome::Undecideable) => {
-                                BetState::BettorsDrew
-                            }
-                            (BetOutcome::Bettor1Wins, BetOutcome::Bettor1Wins) => {
-                                BetState::Bettor1Wins
-                            }
-                            (BetOutcome::Bettor2Wins, BetOutcome::Bettor2Wins) => {
-                                BetState::Bettor2Wins
-                            }
-                            _ => BetState::BettorsDisagree,
-                        }
-                    } else if bet.state == BetState::BetAcceptedByBettor2 {
-                        bet.outcome_claimed_by_bettor_2 = Some(outcome);
-                        bet.state = BetState::Bettor2Voted;
-                    } else {
-                        return Err(Error::InvalidStateForCallingFunction);
-                    }
-                    return Ok(());
-                }
-                _ => {}
+        /// The maker's stake the contract holds for an offer until it is matched, in the offer's
+        /// currency
+        fn stake_escrowed_in_offer(offer: &Offer) -> Balance {
+            match offer.state {
+                OfferState::Open => offer.remaining_stake_bettor_1,
+                OfferState::Filled | OfferState::Cancelled => 0,
             }
+        }
 
-            Err(Error::CallerNotValidBettor)
+        /// The stakes the contract holds for a pool until they are claimed, in the pool's currency
+        fn stakes_escrowed_in(pool: &Pool) -> Balance {
+            match pool.state {
+                PoolState::PayingOut => pool.unpaid_payout,
+                _ => pool.total_staked,
+            }
         }
 
-        #[ink(message, payable)]
-        pub fn withdraw_winnings(&mut self, n: u32) -> Result<bool, Error> {
-            // update bet's state to "concluded"
-            let x = self.bets.get(n as usize);
-            if x.is_none() {
-                return Err(Error::BetDoesNotExist);
-            }
-
-            // pay winnings
-            let mut concluded = true;
-            let mut yet_to_pay_1 = false;
-            let mut yet_to_pay_2 = false;
-            match x {
-                None => {}
-                Some(bet) => {
-                    // if bettors agree on outcome, send money to correct better(s)
-                    match bet.state {
-                        BetState::Bettor1Wins | BetState::YetToPayBettor1 => {
-                            concluded = self
-                                .env()
-                                .transfer(bet.bettor_1.unwrap(), 2 * bet.amount_wagered)
-                                .is_ok();
-                        }
-                        BetState::Bettor2Wins | BetState::YetToPayBettor2 => {
-                            concluded = self
-                                .env()
-                                .transfer(bet.bettor_2.unwrap(), 2 * bet.amount_wagered)
-                                .is_ok();
-                        }
-                        BetState::BettorsDrew => {
-                            if self
-                                .env()
-                                .transfer(bet.bettor_1.unwrap(), bet.amount_wagered)
-                                .is_err()
-                            {
-                                yet_to_pay_1 = true;
-                                concluded = false;
-                            }
-                            if self
-                                .env()
-                                .transfer(bet.bettor_2.unwrap(), bet.amount_wagered)
-                                .is_err()
-                            {
-                                yet_to_pay_2 = true;
-                                concluded = false;
-                            }
-                        }
-                        BetState::BettorsDisagree => {}
-                        BetState::Concluded => return Err(Error::AlreadyWithdrewWinnings),
-                        _ => {}
-                    }
-                }
+        /// The dispute fee or appeal bond the contract holds for a pool, in the native currency
+        fn pool_fees_escrowed_in(pool: &Pool) -> Balance {
+            match pool.state {
+                PoolState::ReviewerAssigned | PoolState::DisputeEscalated => pool.dispute_fee_paid,
+                PoolState::RulingAppealed => pool.appeal_bond_paid,
+                _ => 0,
             }
+        }
 
-            let bet = self.bets.get_mut(n as usize).unwrap();
-            match concluded {
-                true => {
-                    bet.state = BetState::Concluded;
+        /// Hold another `amount` of `token`, or of the native currency, for unsettled bets
+        fn escrow(&mut self, token: Option<AccountId>, amount: Balance) -> Result<(), Error> {
+            match token {
+                None => {
+                    self.total_escrowed = self
+                        .total_escrowed
+                        .checked_add(amount)
+                        .ok_or(Error::Overflow)?;
                 }
-                false => {
-                    if bet.state == BetState::BettorsDrew {
-                        if yet_to_pay_1 && yet_to_pay_2 {
-                            ();
-                        } else if yet_to_pay_1 {
-                            bet.state = BetState::YetToPayBettor1
-                        } else if yet_to_pay_2 {
-                            bet.state = BetState::YetToPayBettor2
-                        }
-                    }
-                    // else, do nothing
+                Some(token) => {
+                    let escrowed = self
+                        .token_escrowed
+                        .get(token)
+                        .unwrap_or_default()
+                        .checked_add(amount)
+                        .ok_or(Error::Overflow)?;
+                    self.token_escrowed.insert(token, &escrowed);
                 }
             }
+            Ok(())
+        }
 
-            Ok(concluded)
+        /// Stop holding `amount` of `token`, or of the native currency, once a bet is settled
+        fn release_escrow(&mut self, token: Option<AccountId>, amount: Balance) {
+            match token {
+                None => self.total_escrowed -= amount,
+                Some(token) => {
+                    let escrowed = self.token_escrowed.get(token).unwrap_or_default();
+                    self.token_escrowed.insert(token, &(escrowed - amount));
+                }
+            }
         }
 
-        /// Get amount wagered
+        /// Get how much a referrer has earned and not yet withdrawn
         #[ink(message)]
-        pub fn get_amount_wagered(&self, n: u32) -> Result<Balance, Error> {
-            match self.bets.get(n as usize) {
-                Some(x) => Ok(x.amount_wagered),
-                None => Err(Error::BetDoesNotExist),
-            }
+        pub fn get_referral_rewards(&self, referrer: AccountId) -> Balance {
+            self.referral_rewards.get(referrer).unwrap_or_default()
         }
 
-        /// Get datetime of when even finishes by
+        /// (For referrers): Withdraw the share of bet creation fees earned from referrals
         #[ink(message)]
-        pub fn get_event_decided_by(&self, n: u32) -> Result<String, Error> {
-            match self.bets.get(n as usize) {
-                Some(x) => Ok(x.event_decided_by.clone()),
-                None => Err(Error::BetDoesNotExist),
+        pub fn withdraw_referral_rewards(&mut self) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+            let rewards = self.referral_rewards.take(caller).unwrap_or_default();
+            self.total_referral_rewards -= rewards;
+            if rewards > 0 && self.env().transfer(caller, rewards).is_err() {
+                return Err(Error::TransferFailed);
             }
+            self.debug_assert_solvent();
+            Ok(rewards)
         }
 
-        /// Get datetime of when even finishes by
+        /// Get how much an account is owed and can claim
         #[ink(message)]
-        pub fn get_event_decided_by_as_ms(&self, n: u32) -> Result<i64, Error> {
-            match self.bets.get(n as usize) {
-                Some(x) => self.convert_datetime_to_ms(x.event_decided_by.clone()),
-                None => Err(Error::BetDoesNotExist),
-            }
+        pub fn claimable_of(&self, account: AccountId) -> Balance {
+            self.claimable.get(account).unwrap_or_default()
         }
 
-        /// Get bet state
+        /// Withdraw `amount` of what you are owed
         #[ink(message)]
-        pub fn get_bet_state(&self, n: u32) -> Result<BetState, Error> {
-            match self.bets.get(n as usize) {
-                Some(x) => Ok(x.state),
-                None => Err(Error::BetDoesNotExist),
+        pub fn claim(&mut self, amount: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let claimable = self.claimable_of(caller);
+            if amount > claimable {
+                return Err(Error::InsufficientClaimableBalance);
+            }
+            self.claimable.insert(caller, &(claimable - amount));
+            self.total_claimable -= amount;
+            if amount > 0 && self.env().transfer(caller, amount).is_err() {
+                return Err(Error::TransferFailed);
             }
+            self.debug_assert_solvent();
+            Ok(())
         }
 
-        /// Get criteria for winning
-        #[ink(message)]
-        pub fn get_criteria_for_winning(&self, n: u32) -> Result<String, Error> {
-            match self.bets.get(n as usize) {
-                Some(x) => Ok(x.criteria_for_winning.clone()),
-                None => Err(Error::BetDoesNotExist),
+        /// Record that the contract owes `account` another `amount`, for it to claim
+        fn credit(&mut self, account: AccountId, amount: Balance) -> Result<(), Error> {
+            if amount > 0 {
+                let claimable = self
+                    .claimable_of(account)
+                    .checked_add(amount)
+                    .ok_or(Error::Overflow)?;
+                let total_claimable = self
+                    .total_claimable
+                    .checked_add(amount)
+                    .ok_or(Error::Overflow)?;
+                self.claimable.insert(account, &claimable);
+                self.total_claimable = total_claimable;
             }
+            Ok(())
         }
 
-        /// Get bettor account id
-        #[ink(message)]
-        pub fn get_bettor_account_id(
+        /// Record that the contract owes `account` another `amount` of `token`, or of the
+        /// native currency, for it to claim
+        fn credit_in(
+            &mut self,
+            token: Option<AccountId>,
+            account: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let token = match token {
+                Some(x) => x,
+                None => return self.credit(account, amount),
+            };
+            let claimable = self
+                .token_claimable_of(token, account)
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            let total_claimable = self
+                .token_total_claimable
+                .get(token)
+                .unwrap_or_default()
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            self.token_claimable.insert((token, account), &claimable);
+            self.token_total_claimable.insert(token, &total_claimable);
+            Ok(())
+        }
+
+        /// Get how much of a PSP22 token an account is owed and can claim
+        #[ink(message)]
+        pub fn token_claimable_of(&self, token: AccountId, account: AccountId) -> Balance {
+            self.token_claimable
+                .get((token, account))
+                .unwrap_or_default()
+        }
+
+        /// Withdraw `amount` of what you are owed in a PSP22 token
+        #[ink(message)]
+        pub fn claim_tokens(&mut self, token: AccountId, amount: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let claimable = self.token_claimable_of(token, caller);
+            if amount > claimable {
+                return Err(Error::InsufficientClaimableBalance);
+            }
+            // each account's claimable balance is part of the token's total
+            let total_claimable = self.token_total_claimable.get(token).unwrap_or_default();
+            self.token_claimable
+                .insert((token, caller), &(claimable - amount));
+            self.token_total_claimable
+                .insert(token, &(total_claimable - amount));
+            self.send_tokens(token, caller, amount)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the fees the platform has kept in a PSP22 token
+        #[ink(message)]
+        pub fn get_token_treasury_balance(&self, token: AccountId) -> Balance {
+            self.token_treasury.get(token).unwrap_or_default()
+        }
+
+        /// (For the admin): Withdraw `amount` of the fees kept in a PSP22 token to `to`
+        #[ink(message)]
+        pub fn withdraw_token_fees(
+            &mut self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            self.only_admin()?;
+            let treasury_balance = self.get_token_treasury_balance(token);
+            if amount > treasury_balance {
+                return Err(Error::InsufficientTreasuryBalance);
+            }
+            let treasury_balance = treasury_balance - amount;
+            self.token_treasury.insert(token, &treasury_balance);
+            self.send_tokens(token, to, amount)?;
+            self.env().emit_event(FeesWithdrawn {
+                to,
+                token: Some(token),
+                amount,
+                treasury_balance,
+            });
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Pull `amount` of `token` from `from` into the contract, against an allowance `from`
+        /// gave the contract. The native currency comes with the call instead.
+        fn receive_tokens(
             &self,
-            n: u32,
-            bettor: u8,
-        ) -> Result<Option<AccountId>, Error> {
-            match self.bets.get(n as usize) {
-                Some(x) => match bettor {
-                    1 => Ok(x.bettor_1),
-                    2 => Ok(x.bettor_2),
-                    _ => Err(Error::BettorDoesNotExist),
+            token: Option<AccountId>,
+            from: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            match token {
+                Some(token) if amount > 0 => psp22::transfer_from(
+                    self.token_transport,
+                    token,
+                    from,
+                    self.env().account_id(),
+                    amount,
+                ),
+                _ => Ok(()),
+            }
+        }
+
+        /// Send `amount` of `token` from the contract to `to`
+        fn send_tokens(
+            &self,
+            token: AccountId,
+            to: AccountId,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            if amount == 0 {
+                return Ok(());
+            }
+            psp22::transfer(self.token_transport, token, to, amount)
+        }
+
+        /// (For the final decision maker): Set the shares of each bet creation fee (in basis
+        /// points) going to the reviewer reward pool and to referrers, and how much the
+        /// reviewers resolving a dispute are paid from the pool
+        #[ink(message)]
+        pub fn set_fee_distribution(
+            &mut self,
+            reviewer_pool_basis_points: u16,
+            referrer_basis_points: u16,
+            dispute_reward: Balance,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if reviewer_pool_basis_points as u128 + referrer_basis_points as u128 > BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.reviewer_pool_basis_points = reviewer_pool_basis_points;
+            self.referrer_basis_points = referrer_basis_points;
+            self.dispute_reward = dispute_reward;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the reviewer pool and referrer shares (in basis points) and the dispute reward
+        #[ink(message)]
+        pub fn get_fee_distribution(&self) -> (u16, u16, Balance) {
+            (
+                self.reviewer_pool_basis_points,
+                self.referrer_basis_points,
+                self.dispute_reward,
+            )
+        }
+
+        /// (For the final decision maker): Set the share of the bet creation fee (in basis points)
+        /// refunded to bettor 1 when bettor 2 rejects the bet
+        #[ink(message)]
+        pub fn set_rejection_refund_config(&mut self, basis_points: u16) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if basis_points as u128 > BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.rejection_refund_basis_points = basis_points;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the share of the bet creation fee (in basis points) refunded on rejected bets
+        #[ink(message)]
+        pub fn get_rejection_refund_config(&self) -> u16 {
+            self.rejection_refund_basis_points
+        }
+
+        /// How much of a rejected bet's creation fee goes back to bettor 1. It comes out of
+        /// the treasury first and then the reviewer reward pool, so it is capped by what they hold.
+        /// Fees paid in a token only ever went to the token's treasury.
+        fn rejection_fee_refund(&self, bet: &Bet) -> Balance {
+            let refund = Self::basis_points_of(
+                bet.bet_creation_fee_paid,
+                self.rejection_refund_basis_points,
+            );
+            let available = match bet.token {
+                None => self
+                    .treasury_balance
+                    .saturating_add(self.reviewer_reward_pool),
+                Some(token) => self.get_token_treasury_balance(token),
+            };
+            refund.min(available)
+        }
+
+        /// Take a refunded bet creation fee out of the treasury and then the reviewer reward pool
+        fn take_rejection_fee_refund(&mut self, token: Option<AccountId>, refund: Balance) {
+            if let Some(token) = token {
+                let treasury_balance = self.get_token_treasury_balance(token);
+                self.token_treasury
+                    .insert(token, &(treasury_balance - refund));
+                return;
+            }
+            let from_treasury = refund.min(self.treasury_balance);
+            self.treasury_balance -= from_treasury;
+            self.reviewer_reward_pool -= refund - from_treasury;
+        }
+
+        /// Split a fee into the reviewer reward pool, the referrer's rewards and the treasury,
+        /// which gets whatever is left
+        fn distribute_fee(
+            &mut self,
+            fee: Balance,
+            referrer: Option<AccountId>,
+        ) -> Result<(), Error> {
+            let to_reviewer_pool = Self::basis_points_of(fee, self.reviewer_pool_basis_points);
+            let reviewer_reward_pool = self
+                .reviewer_reward_pool
+                .checked_add(to_reviewer_pool)
+                .ok_or(Error::Overflow)?;
+
+            // the shares add up to at most 10,000 basis points, so these cannot underflow
+            let mut to_treasury = fee - to_reviewer_pool;
+            let mut referral = None;
+            if let Some(referrer) = referrer {
+                let to_referrer = Self::basis_points_of(fee, self.referrer_basis_points);
+                let rewards = self
+                    .get_referral_rewards(referrer)
+                    .checked_add(to_referrer)
+                    .ok_or(Error::Overflow)?;
+                let total_referral_rewards = self
+                    .total_referral_rewards
+                    .checked_add(to_referrer)
+                    .ok_or(Error::Overflow)?;
+                referral = Some((referrer, rewards, total_referral_rewards));
+                to_treasury -= to_referrer;
+            }
+            let treasury_balance = self
+                .treasury_balance
+                .checked_add(to_treasury)
+                .ok_or(Error::Overflow)?;
+
+            self.reviewer_reward_pool = reviewer_reward_pool;
+            if let Some((referrer, rewards, total_referral_rewards)) = referral {
+                self.referral_rewards.insert(referrer, &rewards);
+                self.total_referral_rewards = total_referral_rewards;
+            }
+            self.treasury_balance = treasury_balance;
+            Ok(())
+        }
+
+        /// Split a fee paid in `token`, or in the native currency, like `distribute_fee`.
+        ///   The reviewer reward pool pays out in the native currency, so its share of a token
+        ///   fee stays in the token's treasury, and the referrer's share is theirs to claim.
+        fn distribute_fee_in(
+            &mut self,
+            token: Option<AccountId>,
+            fee: Balance,
+            referrer: Option<AccountId>,
+        ) -> Result<(), Error> {
+            let token = match token {
+                Some(x) => x,
+                None => return self.distribute_fee(fee, referrer),
+            };
+            let mut to_treasury = fee;
+            if let Some(referrer) = referrer {
+                let to_referrer = Self::basis_points_of(fee, self.referrer_basis_points);
+                self.credit_in(Some(token), referrer, to_referrer)?;
+                to_treasury -= to_referrer;
+            }
+            let treasury_balance = self
+                .get_token_treasury_balance(token)
+                .checked_add(to_treasury)
+                .ok_or(Error::Overflow)?;
+            self.token_treasury.insert(token, &treasury_balance);
+            Ok(())
+        }
+
+        /// Get current block timestamp
+        #[ink(message)]
+        pub fn get_current_block_timestamp(&self) -> Result<u64, Error> {
+            Ok(self.env().block_timestamp())
+        }
+
+        /// Convert datetime to milliseconds since Unix epoch
+        #[ink(message)]
+        pub fn convert_datetime_to_ms(&self, dt: String) -> Result<i64, Error> {
+            match dt.as_str().parse::<DateTime<Utc>>() {
+                Ok(y) => Ok(y.timestamp_millis()),
+                Err(_) => Err(Error::NotDatetimeString),
+            }
+        }
+
+        /// Check if current datetime is past specified datetime
+        #[ink(message)]
+        pub fn has_past_datetime(&self, dt: String) -> Result<bool, Error> {
+            let now = self.env().block_timestamp();
+            match dt.as_str().parse::<DateTime<Utc>>() {
+                Ok(y) => Ok(now as i128 > y.timestamp_millis() as i128),
+                Err(_) => Err(Error::NotDatetimeString),
+            }
+        }
+
+        // --------------------------------------------------------
+        // Bet-related functions
+        // --------------------------------------------------------
+        /// (For bettor 1): Create a bet, sending the creation fee and your stake.
+        ///   Bettor 2 has to stake `stake_bettor_2` to accept it, and the winner takes both.
+        ///   With `commit_reveal`, outcomes have to be committed before they are revealed,
+        ///   so neither bettor sees the other's claim before making their own.
+        ///   The fee bettor 1 pays is given by `quote_fee`.
+        ///   A `referrer` earns a share of the fees on the bet.
+        ///   A bet over labelled `outcomes`, such as race positions or candidates, is won by
+        ///   bettor 1 if one of their `backed_outcomes` happens. Leave both empty for a bet on
+        ///   which bettor wins.
+        ///   With a PSP22 `token`, the wager and fee are pulled from bettor 1 against an
+        ///   allowance they gave the contract, and the bet settles in the token. Dispute fees
+        ///   and appeal bonds are paid in the native currency either way.
+        #[ink(message, payable)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_bet(
+            &mut self,
+            stake_bettor_1: Balance,
+            stake_bettor_2: Balance,
+            bettor_2: Option<AccountId>,
+            criteria_for_winning: String,
+            event_decided_by: String,
+            commit_reveal: bool,
+            referrer: Option<AccountId>,
+            token: Option<AccountId>,
+            outcomes: Vec<String>,
+            backed_outcomes: Vec<u8>,
+        ) -> Result<Option<u32>, Error> {
+            let next_bet = self.latest_bet.checked_add(1).ok_or(Error::Overflow)?;
+            let bet = self.propose_bet(
+                stake_bettor_1,
+                stake_bettor_2,
+                bettor_2,
+                criteria_for_winning,
+                event_decided_by,
+                commit_reveal,
+                referrer,
+                token,
+                &outcomes,
+                backed_outcomes,
+            )?;
+            self.collect_stake_of_bettor_1(&bet)?;
+
+            // update latest bet number
+            let bet_number = self.latest_bet;
+            self.latest_bet = next_bet;
+
+            self.bets.insert(bet_number, &bet);
+            for (outcome, label) in outcomes.iter().enumerate() {
+                self.outcome_labels
+                    .insert((bet_number, outcome as u8), label);
+            }
+            self.debug_assert_solvent();
+            Ok(Some(bet_number))
+        }
+
+        /// The bet the caller proposes as bettor 1 (see `create_bet`), once its terms are
+        /// checked. Bettor 1's fee is quoted into `bet_creation_fee_paid`.
+        #[allow(clippy::too_many_arguments)]
+        fn propose_bet(
+            &self,
+            stake_bettor_1: Balance,
+            stake_bettor_2: Balance,
+            bettor_2: Option<AccountId>,
+            criteria_for_winning: String,
+            event_decided_by: String,
+            commit_reveal: bool,
+            referrer: Option<AccountId>,
+            token: Option<AccountId>,
+            outcomes: &[String],
+            backed_outcomes: Vec<u8>,
+        ) -> Result<Bet, Error> {
+            let caller = self.env().caller();
+            if stake_bettor_1 == 0 || stake_bettor_2 == 0 {
+                return Err(Error::InvalidStake);
+            }
+            if criteria_for_winning.len() > MAX_CRITERIA_LENGTH
+                || event_decided_by.len() > MAX_CRITERIA_LENGTH
+            {
+                return Err(Error::CriteriaTooLong);
+            }
+            let quote = self.quote_fee(stake_bettor_1, stake_bettor_2)?;
+            if referrer == Some(caller) {
+                return Err(Error::SelfReferral);
+            }
+            if outcomes.is_empty() {
+                if !backed_outcomes.is_empty() {
+                    return Err(Error::InvalidBacking);
+                }
+            } else {
+                if !(2..=MAX_OUTCOMES as usize).contains(&outcomes.len()) {
+                    return Err(Error::InvalidNumberOfOutcomes);
+                }
+                if outcomes
+                    .iter()
+                    .any(|label| label.len() > MAX_OUTCOME_LABEL_LENGTH)
+                {
+                    return Err(Error::OutcomeLabelTooLong);
+                }
+                Self::check_backing(&backed_outcomes, outcomes.len(), &[])?;
+                // bettor 2 has to be left something to back
+                if backed_outcomes.len() == outcomes.len() {
+                    return Err(Error::InvalidBacking);
+                }
+            }
+            let decided_by_ms = match event_decided_by.as_str().parse::<DateTime<Utc>>() {
+                Ok(y) => y.timestamp_millis().max(0) as Timestamp,
+                Err(_) => return Err(Error::NotDatetimeString),
+            };
+            let (commit_deadline, reveal_deadline) = if commit_reveal {
+                let commit_deadline = decided_by_ms
+                    .checked_add(self.commit_period)
+                    .ok_or(Error::Overflow)?;
+                let reveal_deadline = commit_deadline
+                    .checked_add(self.reveal_period)
+                    .ok_or(Error::Overflow)?;
+                (Some(commit_deadline), Some(reveal_deadline))
+            } else {
+                (None, None)
+            };
+
+            Ok(Bet {
+                stake_bettor_1,
+                stake_bettor_2,
+                token,
+                bet_creation_fee_paid: quote.bettor_1_fee,
+                acceptance_fee: quote.bettor_2_fee,
+                rake_basis_points: self.rake_basis_points(),
+                bettor_1: Some(caller),
+                bettor_2,
+                criteria_for_winning,
+                event_decided_by,
+                // there are at most `MAX_OUTCOMES` of them
+                number_of_outcomes: outcomes.len() as u8,
+                offer: None,
+                outcomes_backed_by_bettor_1: backed_outcomes,
+                outcomes_backed_by_bettor_2: Vec::new(),
+                state: BetState::Created,
+                outcome_claimed_by_bettor_1: None,
+                outcome_claimed_by_bettor_2: None,
+                referrer,
+                commit_deadline,
+                reveal_deadline,
+                outcome_commitment_by_bettor_1: None,
+                outcome_commitment_by_bettor_2: None,
+                reviewers: Vec::new(),
+                reviewer_votes: Vec::new(),
+                recused_reviewers: Vec::new(),
+                evidence_deadline: None,
+                evidence_submitted_by_bettor_1: 0,
+                evidence_submitted_by_bettor_2: 0,
+                ruling_deadline: None,
+                overdue_reviewers: Vec::new(),
+                reassignments: 0,
+                outcome_claimed_by_reviewer: None,
+                dispute_requested_by: None,
+                dispute_fee_paid: 0,
+                reviewer_ruled_at: None,
+                appellant: None,
+                appeal_bond_paid: 0,
+                outcome_decided_by_final_decision_maker: None,
+            })
+        }
+
+        /// Collect bettor 1's stake and fee on a proposed bet from the caller, escrowing the
+        /// stake and crediting back anything sent beyond what is due
+        fn collect_stake_of_bettor_1(&mut self, bet: &Bet) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let amount_due = bet
+                .bet_creation_fee_paid
+                .checked_add(bet.stake_bettor_1)
+                .ok_or(Error::Overflow)?;
+            let native_due = match bet.token {
+                None => amount_due,
+                Some(_) => 0,
+            };
+            if transferred_amount < native_due {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            self.receive_tokens(bet.token, caller, amount_due)?;
+            self.escrow(bet.token, bet.stake_bettor_1)?;
+            self.distribute_fee_in(bet.token, bet.bet_creation_fee_paid, bet.referrer)?;
+            self.credit(caller, transferred_amount - native_due)
+        }
+
+        #[ink(message)]
+        pub fn reject_bet(&mut self, n: u32) -> Result<bool, Error> {
+            let caller = self.env().caller();
+
+            match self.bets.get(n) {
+                Some(mut x) => match x.bettor_2 {
+                    Some(bettor) => {
+                        if bettor != caller {
+                            return Err(Error::NotBettor2);
+                        }
+                        if x.state == BetState::Cancelled {
+                            return Err(Error::BetCancelled);
+                        }
+                        if x.state != BetState::Created {
+                            return Err(Error::InvalidStateForCallingFunction);
+                        }
+                        Self::set_state(&mut x, BetState::BetRefusedByBettor2)?;
+                        self.bets.insert(n, &x);
+                        self.debug_assert_solvent();
+                        Ok(true)
+                    }
+                    None => Err(Error::NotBettor2),
                 },
                 None => Err(Error::BetDoesNotExist),
             }
         }
 
-        // --------------------------------------------------------
-        // Reputation-related functions
-        // --------------------------------------------------------
-        #[ink(message)]
-        pub fn register_as_reviewer(&mut self) -> Result<(), ()> {
-            self.reviewers.insert(self.env().caller(), &());
-            self.number_of_reviewers += 1;
-            Ok(())
-        }
+        /// (For bettor 1): Cancel a bet nobody has accepted yet, getting the wager back to claim.
+        ///   The bet creation fee is kept.
+        #[ink(message)]
+        pub fn cancel_bet(&mut self, n: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            if bet.bettor_1 != Some(caller) {
+                return Err(Error::NotBettor1);
+            }
+            if bet.state != BetState::Created {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+
+            Self::set_state(&mut bet, BetState::Cancelled)?;
+            self.bets.insert(n, &bet);
+            let (token, refund) = (bet.token, bet.stake_bettor_1);
+            self.release_escrow(token, refund);
+            self.credit_in(token, caller, refund)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For bettor 2): Accept a bet, sending your stake and the acceptance fee.
+        ///   On a token bet they are pulled from bettor 2 against an allowance instead.
+        ///   On a bet over labelled outcomes, bettor 2 backs `backed_outcomes`, or every outcome
+        ///   bettor 1 did not back if it is empty. An outcome neither of them backs is a draw.
+        #[ink(message, payable)]
+        pub fn accept_bet(&mut self, n: u32, backed_outcomes: Vec<u8>) -> Result<bool, Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let x = &bet;
+            // make sure bettor2 candidate sent enough tokens
+            let amount_due = x
+                .stake_bettor_2
+                .checked_add(x.acceptance_fee)
+                .ok_or(Error::Overflow)?;
+            let native_due = match x.token {
+                None => amount_due,
+                Some(_) => 0,
+            };
+            if transferred_amount < native_due {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            if x.state == BetState::Cancelled {
+                return Err(Error::BetCancelled);
+            }
+            if x.state != BetState::Created {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+
+            // allow caller to accept bet if either
+            //   1. bettor2 has not been assigned by bettor1 or
+            //   2. bettor2 has been assigned by bettor1 and is caller
+            if matches!(x.bettor_2, Some(bettor) if bettor != caller) {
+                return Err(Error::NotBettor2);
+            }
+            let backed_outcomes = Self::backing_of_bettor_2(x, backed_outcomes)?;
+            let (bettor_1, stake) = (x.bettor_1.unwrap(), x.stake_bettor_2);
+            let (token, fee, referrer) = (x.token, x.acceptance_fee, x.referrer);
+            self.receive_tokens(token, caller, amount_due)?;
+
+            Self::set_state(&mut bet, BetState::BetAcceptedByBettor2)?;
+            bet.bettor_2 = Some(caller);
+            bet.outcomes_backed_by_bettor_2 = backed_outcomes;
+            self.bets.insert(n, &bet);
+            let now = self.env().block_timestamp();
+            self.last_bet_between
+                .insert(Self::account_pair(bettor_1, caller), &now);
+            self.escrow(token, stake)?;
+            self.distribute_fee_in(token, fee, referrer)?;
+            self.credit(caller, transferred_amount - native_due)?;
+            self.debug_assert_solvent();
+            Ok(true)
+        }
+
+        /// (For bettors): Submit event's outcome.
+        ///   winner = 0 (draw), 1 (bettor1 wins), 2 (bettor2 wins) or 3 (undecideable)
+        ///   On a bet over labelled outcomes, winner is the index of the outcome that happened,
+        ///   or the number of outcomes if it cannot be decided.
+        #[ink(message)]
+        pub fn submit_outcome(&mut self, n: u32, winner: u8) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let evidence_deadline = self.deadline_after(self.evidence_period)?;
+            let mut bet = match self.bets.get(n) {
+                Some(y) => y,
+                None => {
+                    return Err(Error::BetDoesNotExist);
+                }
+            };
+            if bet.commit_deadline.is_some() {
+                return Err(Error::CommitRevealBet);
+            }
+
+            // figure out what state `winner` corresponds to
+            let outcome = Self::outcome_from_winner(&bet, winner)?;
+
+            // check if caller is bettor 1
+            if bet.bettor_1.unwrap() == caller {
+                // update state
+                if bet.state == BetState::Bettor2Voted {
+                    bet.outcome_claimed_by_bettor_1 = Some(outcome);
+                    let state =
+                        Self::state_for_claims(outcome, bet.outcome_claimed_by_bettor_2.unwrap());
+                    Self::set_state(&mut bet, state)?;
+                    Self::open_evidence_period(&mut bet, evidence_deadline);
+                } else if bet.state == BetState::BetAcceptedByBettor2 {
+                    bet.outcome_claimed_by_bettor_1 = Some(outcome);
+                    Self::set_state(&mut bet, BetState::Bettor1Voted)?;
+                } else {
+                    return Err(Error::InvalidStateForCallingFunction);
+                }
+
+                self.bets.insert(n, &bet);
+                self.debug_assert_solvent();
+                return Ok(());
+            }
+
+            // check if caller is bettor 2
+            match bet.bettor_2 {
+                Some(bettor_2) if bettor_2 == caller => {
+                    // update state
+                    if bet.state == BetState::Bettor1Voted {
+                        bet.outcome_claimed_by_bettor_2 = Some(outcome);
+                        let state = Self::state_for_claims(
+                            bet.outcome_claimed_by_bettor_1.unwrap(),
+                            outcome,
+                        );
+                        Self::set_state(&mut bet, state)?;
+                        Self::open_evidence_period(&mut bet, evidence_deadline);
+                    } else if bet.state == BetState::BetAcceptedByBettor2 {
+                        bet.outcome_claimed_by_bettor_2 = Some(outcome);
+                        Self::set_state(&mut bet, BetState::Bettor2Voted)?;
+                    } else {
+                        return Err(Error::InvalidStateForCallingFunction);
+                    }
+                    self.bets.insert(n, &bet);
+                    self.debug_assert_solvent();
+                    return Ok(());
+                }
+                _ => {}
+            }
+
+            Err(Error::CallerNotValidBettor)
+        }
+
+        /// (For bettors on commit-reveal bets): Commit to the event's outcome without revealing it.
+        ///   commitment = `get_outcome_commitment(n, caller, winner, salt)`
+        #[ink(message)]
+        pub fn commit_outcome(&mut self, n: u32, commitment: Hash) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let commit_deadline = match bet.commit_deadline {
+                Some(x) => x,
+                None => return Err(Error::NotCommitRevealBet),
+            };
+            if bet.state != BetState::BetAcceptedByBettor2 {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if now > commit_deadline {
+                return Err(Error::CommitPhaseClosed);
+            }
+
+            let slot = if bet.bettor_1 == Some(caller) {
+                &mut bet.outcome_commitment_by_bettor_1
+            } else if bet.bettor_2 == Some(caller) {
+                &mut bet.outcome_commitment_by_bettor_2
+            } else {
+                return Err(Error::CallerNotValidBettor);
+            };
+            if slot.is_some() {
+                return Err(Error::AlreadyCommitted);
+            }
+            *slot = Some(commitment);
+            self.bets.insert(n, &bet);
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For bettors on commit-reveal bets): Reveal the outcome committed to.
+        ///   Revealing opens once both bettors committed or the commit phase closed.
+        ///   The bet settles as soon as every commitment is revealed.
+        #[ink(message)]
+        pub fn reveal_outcome(&mut self, n: u32, winner: u8, salt: [u8; 32]) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            let evidence_deadline = self.deadline_after(self.evidence_period)?;
+            let revealed = self.get_outcome_commitment(n, caller, winner, salt);
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let (commit_deadline, reveal_deadline) =
+                match (bet.commit_deadline, bet.reveal_deadline) {
+                    (Some(commit), Some(reveal)) => (commit, reveal),
+                    _ => return Err(Error::NotCommitRevealBet),
+                };
+            if bet.state != BetState::BetAcceptedByBettor2 {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            let outcome = Self::outcome_from_winner(&bet, winner)?;
+            let both_committed = bet.outcome_commitment_by_bettor_1.is_some()
+                && bet.outcome_commitment_by_bettor_2.is_some();
+            if !both_committed && now <= commit_deadline {
+                return Err(Error::RevealPhaseNotOpen);
+            }
+            if now > reveal_deadline {
+                return Err(Error::RevealPhaseClosed);
+            }
+
+            let (commitment, claim) = if bet.bettor_1 == Some(caller) {
+                (
+                    bet.outcome_commitment_by_bettor_1,
+                    &mut bet.outcome_claimed_by_bettor_1,
+                )
+            } else if bet.bettor_2 == Some(caller) {
+                (
+                    bet.outcome_commitment_by_bettor_2,
+                    &mut bet.outcome_claimed_by_bettor_2,
+                )
+            } else {
+                return Err(Error::CallerNotValidBettor);
+            };
+            match commitment {
+                None => return Err(Error::NothingCommitted),
+                Some(_) if claim.is_some() => return Err(Error::AlreadyRevealed),
+                Some(x) if x != revealed => return Err(Error::CommitmentMismatch),
+                Some(_) => *claim = Some(outcome),
+            }
+
+            let all_revealed = (bet.outcome_commitment_by_bettor_1.is_none()
+                || bet.outcome_claimed_by_bettor_1.is_some())
+                && (bet.outcome_commitment_by_bettor_2.is_none()
+                    || bet.outcome_claimed_by_bettor_2.is_some());
+            if all_revealed {
+                Self::settle_revealed_claims(&mut bet, evidence_deadline)?;
+            }
+            self.bets.insert(n, &bet);
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Settle a commit-reveal bet after its reveal phase closed.
+        ///   A bettor who did not reveal has the other side's claim stand.
+        #[ink(message)]
+        pub fn close_reveal_phase(&mut self, n: u32) -> Result<(), Error> {
+            let now = self.env().block_timestamp();
+            let evidence_deadline = self.deadline_after(self.evidence_period)?;
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let reveal_deadline = match bet.reveal_deadline {
+                Some(x) => x,
+                None => return Err(Error::NotCommitRevealBet),
+            };
+            if bet.state != BetState::BetAcceptedByBettor2 {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if now <= reveal_deadline {
+                return Err(Error::RevealPhaseNotOver);
+            }
+
+            Self::settle_revealed_claims(&mut bet, evidence_deadline)?;
+            self.bets.insert(n, &bet);
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For the final decision maker): Set how long (in milliseconds) bettors can commit
+        /// outcomes after an event is decided, and then reveal them, on commit-reveal bets
+        #[ink(message)]
+        pub fn set_commit_reveal_config(
+            &mut self,
+            commit_period: Timestamp,
+            reveal_period: Timestamp,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            self.commit_period = commit_period;
+            self.reveal_period = reveal_period;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the commit and reveal periods (in milliseconds)
+        #[ink(message)]
+        pub fn get_commit_reveal_config(&self) -> (Timestamp, Timestamp) {
+            (self.commit_period, self.reveal_period)
+        }
+
+        /// Get the commit and reveal deadlines of a commit-reveal bet
+        #[ink(message)]
+        pub fn get_commit_reveal_deadlines(
+            &self,
+            n: u32,
+        ) -> Result<Option<(Timestamp, Timestamp)>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.commit_deadline.zip(x.reveal_deadline)),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Whether a bet may move from state `from` to state `to`
+        fn is_valid_transition(from: BetState, to: BetState) -> bool {
+            use BetState::*;
+            match from {
+                Created => matches!(to, BetAcceptedByBettor2 | BetRefusedByBettor2 | Cancelled),
+                // commit-reveal bets settle straight from here once the claims are revealed
+                BetAcceptedByBettor2 => matches!(
+                    to,
+                    Bettor1Voted
+                        | Bettor2Voted
+                        | Bettor1Wins
+                        | Bettor2Wins
+                        | BettorsDrew
+                        | BettorsDisagree
+                ),
+                Bettor1Voted | Bettor2Voted => {
+                    matches!(
+                        to,
+                        Bettor1Wins | Bettor2Wins | BettorsDrew | BettorsDisagree
+                    )
+                }
+                BettorsDisagree => to == ReviewerAssigned,
+                ReviewerAssigned => {
+                    matches!(
+                        to,
+                        Bettor1Wins | Bettor2Wins | BettorsDrew | DisputeEscalated
+                    )
+                }
+                Bettor1Wins | Bettor2Wins | BettorsDrew => matches!(to, RulingAppealed | Concluded),
+                RulingAppealed | DisputeEscalated => {
+                    matches!(to, Bettor1Wins | Bettor2Wins | BettorsDrew)
+                }
+                BetRefusedByBettor2 => to == Concluded,
+                Cancelled | Concluded => false,
+            }
+        }
+
+        /// Move a bet to `state`, as long as the transition table allows it
+        fn set_state(bet: &mut Bet, state: BetState) -> Result<(), Error> {
+            if !Self::is_valid_transition(bet.state, state) {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            bet.state = state;
+            Ok(())
+        }
+
+        /// Map `winner` (see `submit_outcome`) onto an outcome of the bet
+        fn outcome_from_winner(bet: &Bet, winner: u8) -> Result<BetOutcome, Error> {
+            let number_of_outcomes = bet.number_of_outcomes as usize;
+            if number_of_outcomes == 0 {
+                return match winner {
+                    0 => Ok(BetOutcome::Draw),
+                    1 => Ok(BetOutcome::Bettor1Wins),
+                    2 => Ok(BetOutcome::Bettor2Wins),
+                    3 => Ok(BetOutcome::Undecideable),
+                    _ => Err(Error::OutcomeDoesNotExist),
+                };
+            }
+            match winner as usize {
+                x if x == number_of_outcomes => Ok(BetOutcome::Undecideable),
+                x if x > number_of_outcomes => Err(Error::OutcomeDoesNotExist),
+                _ if bet.outcomes_backed_by_bettor_1.contains(&winner) => {
+                    Ok(BetOutcome::Bettor1Wins)
+                }
+                _ if bet.outcomes_backed_by_bettor_2.contains(&winner) => {
+                    Ok(BetOutcome::Bettor2Wins)
+                }
+                _ => Ok(BetOutcome::Draw),
+            }
+        }
+
+        /// The outcomes bettor 2 backs by asking for `backed_outcomes` (see `accept_bet`)
+        fn backing_of_bettor_2(bet: &Bet, backed_outcomes: Vec<u8>) -> Result<Vec<u8>, Error> {
+            match (bet.number_of_outcomes as usize, backed_outcomes.is_empty()) {
+                (0, true) => Ok(Vec::new()),
+                (0, false) => Err(Error::InvalidBacking),
+                (number_of_outcomes, true) => Ok((0..number_of_outcomes as u8)
+                    .filter(|outcome| !bet.outcomes_backed_by_bettor_1.contains(outcome))
+                    .collect()),
+                (number_of_outcomes, false) => {
+                    Self::check_backing(
+                        &backed_outcomes,
+                        number_of_outcomes,
+                        &bet.outcomes_backed_by_bettor_1,
+                    )?;
+                    Ok(backed_outcomes)
+                }
+            }
+        }
+
+        /// Check that `backed` picks at least one of `number_of_outcomes` outcomes, none of
+        /// them twice and none of those `taken` by the other bettor
+        fn check_backing(
+            backed: &[u8],
+            number_of_outcomes: usize,
+            taken: &[u8],
+        ) -> Result<(), Error> {
+            if backed.is_empty() {
+                return Err(Error::InvalidBacking);
+            }
+            for (i, outcome) in backed.iter().enumerate() {
+                if *outcome as usize >= number_of_outcomes {
+                    return Err(Error::OutcomeDoesNotExist);
+                }
+                if taken.contains(outcome) || backed[..i].contains(outcome) {
+                    return Err(Error::InvalidBacking);
+                }
+            }
+            Ok(())
+        }
+
+        /// The state a bet moves into once both bettors claimed an outcome
+        fn state_for_claims(claim_1: BetOutcome, claim_2: BetOutcome) -> BetState {
+            match (claim_1, claim_2) {
+                (BetOutcome::Draw, BetOutcome::Draw) => BetState::BettorsDrew,
+                (BetOutcome::Undecideable, BetOutcome::Undecideable) => BetState::BettorsDrew,
+                (BetOutcome::Bettor1Wins, BetOutcome::Bettor1Wins) => BetState::Bettor1Wins,
+                (BetOutcome::Bettor2Wins, BetOutcome::Bettor2Wins) => BetState::Bettor2Wins,
+                _ => BetState::BettorsDisagree,
+            }
+        }
+
+        /// Settle a commit-reveal bet on whatever claims were revealed
+        fn settle_revealed_claims(
+            bet: &mut Bet,
+            evidence_deadline: Timestamp,
+        ) -> Result<(), Error> {
+            let state = match (
+                bet.outcome_claimed_by_bettor_1,
+                bet.outcome_claimed_by_bettor_2,
+            ) {
+                (Some(claim_1), Some(claim_2)) => Self::state_for_claims(claim_1, claim_2),
+                (Some(claim), None) | (None, Some(claim)) => Self::state_for_ruling(claim),
+                (None, None) => BetState::BettorsDrew,
+            };
+            Self::set_state(bet, state)?;
+            Self::open_evidence_period(bet, evidence_deadline);
+            Ok(())
+        }
+
+        /// Let the bettors submit evidence until `evidence_deadline` if they disagree
+        fn open_evidence_period(bet: &mut Bet, evidence_deadline: Timestamp) {
+            if bet.state == BetState::BettorsDisagree {
+                bet.evidence_deadline = Some(evidence_deadline);
+            }
+        }
+
+        /// (For bettors and keepers): Settle a decided or rejected bet, crediting what each
+        /// bettor is owed for them to `claim`
+        #[ink(message)]
+        pub fn withdraw_winnings(&mut self, n: u32) -> Result<bool, Error> {
+            let caller = self.env().caller();
+            let mut bet = match self.bets.get(n) {
+                None => return Err(Error::BetDoesNotExist),
+                Some(bet) => bet,
+            };
+            if bet.bettor_1 != Some(caller)
+                && bet.bettor_2 != Some(caller)
+                && !self.is_keeper(caller)
+            {
+                return Err(Error::NotBettorOrKeeper);
+            }
+            if self.is_appealable(&bet) {
+                return Err(Error::AppealWindowOpen);
+            }
+
+            // credit winnings
+            let (bettor_1, bettor_2) = (bet.bettor_1, bet.bettor_2);
+            let (stake_bettor_1, stake_bettor_2) = (bet.stake_bettor_1, bet.stake_bettor_2);
+            let (rake_basis_points, referrer) = (bet.rake_basis_points, bet.referrer);
+            let token = bet.token;
+            let state_before = bet.state;
+            let escrowed = Self::wagers_escrowed_in(&bet);
+            match state_before {
+                BetState::BetRefusedByBettor2 => {
+                    let fee_refund = self.rejection_fee_refund(&bet);
+                    let refund = stake_bettor_1
+                        .checked_add(fee_refund)
+                        .ok_or(Error::Overflow)?;
+                    self.take_rejection_fee_refund(token, fee_refund);
+                    self.credit_in(token, bettor_1.unwrap(), refund)?;
+                }
+                BetState::Bettor1Wins | BetState::Bettor2Wins => {
+                    let (winner, losing_stake) = if state_before == BetState::Bettor1Wins {
+                        (bettor_1, stake_bettor_2)
+                    } else {
+                        (bettor_2, stake_bettor_1)
+                    };
+                    // the rake comes out of the loser's stake, which is what the winner won
+                    let rake = Self::basis_points_of(losing_stake, rake_basis_points);
+                    let pot = Self::pot(stake_bettor_1, stake_bettor_2)?;
+                    self.distribute_fee_in(token, rake, referrer)?;
+                    self.credit_in(token, winner.unwrap(), pot - rake)?;
+                }
+                BetState::BettorsDrew => {
+                    self.credit_in(token, bettor_1.unwrap(), stake_bettor_1)?;
+                    self.credit_in(token, bettor_2.unwrap(), stake_bettor_2)?;
+                }
+                BetState::Cancelled => return Err(Error::BetCancelled),
+                BetState::Concluded => return Err(Error::AlreadyWithdrewWinnings),
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            }
+            self.release_escrow(token, escrowed);
+
+            Self::set_state(&mut bet, BetState::Concluded)?;
+            self.bets.insert(n, &bet);
+
+            // a ruling nobody appealed in time stands once the bet is paid out
+            let ruling_paid_out = state_before != BetState::BetRefusedByBettor2;
+            if ruling_paid_out && bet.reviewer_ruled_at.is_some() && bet.appellant.is_none() {
+                for reviewer in Self::majority_reviewers(&bet) {
+                    self.update_reviewer_stats(reviewer, |stats| {
+                        stats.rulings_upheld = stats.rulings_upheld.saturating_add(1)
+                    });
+                }
+            }
+            self.debug_assert_solvent();
+
+            Ok(true)
+        }
+
+        /// Get the PSP22 token a bet is wagered in, or `None` for the native currency
+        #[ink(message)]
+        pub fn get_token(&self, n: u32) -> Result<Option<AccountId>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.token),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get what bettor 2 has to pay on top of the wager to accept a bet
+        #[ink(message)]
+        pub fn get_acceptance_fee(&self, n: u32) -> Result<Balance, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.acceptance_fee),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get the labelled outcomes a bet is over, which is empty for a bet on which bettor wins
+        #[ink(message)]
+        pub fn get_outcomes(&self, n: u32) -> Result<Vec<String>, Error> {
+            let bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let label = |outcome: u8| match bet.offer {
+                Some(offer) => self.offer_outcome_labels.get((offer, outcome)),
+                None => self.outcome_labels.get((n, outcome)),
+            };
+            Ok((0..bet.number_of_outcomes)
+                .map(|outcome| label(outcome).unwrap_or_default())
+                .collect())
+        }
+
+        /// Get the indices of the outcomes bettor 1 and bettor 2 each back
+        #[ink(message)]
+        pub fn get_backed_outcomes(&self, n: u32) -> Result<(Vec<u8>, Vec<u8>), Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok((
+                    x.outcomes_backed_by_bettor_1.clone(),
+                    x.outcomes_backed_by_bettor_2.clone(),
+                )),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get what bettor 1 and bettor 2 each stake
+        #[ink(message)]
+        pub fn get_stakes(&self, n: u32) -> Result<(Balance, Balance), Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok((x.stake_bettor_1, x.stake_bettor_2)),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get datetime of when even finishes by
+        #[ink(message)]
+        pub fn get_event_decided_by(&self, n: u32) -> Result<String, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.event_decided_by.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get datetime of when even finishes by
+        #[ink(message)]
+        pub fn get_event_decided_by_as_ms(&self, n: u32) -> Result<i64, Error> {
+            match self.bets.get(n) {
+                Some(x) => self.convert_datetime_to_ms(x.event_decided_by.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get bet state
+        #[ink(message)]
+        pub fn get_bet_state(&self, n: u32) -> Result<BetState, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.state),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get criteria for winning
+        #[ink(message)]
+        pub fn get_criteria_for_winning(&self, n: u32) -> Result<String, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.criteria_for_winning.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get bettor account id
+        #[ink(message)]
+        pub fn get_bettor_account_id(
+            &self,
+            n: u32,
+            bettor: u8,
+        ) -> Result<Option<AccountId>, Error> {
+            match self.bets.get(n) {
+                Some(x) => match bettor {
+                    1 => Ok(x.bettor_1),
+                    2 => Ok(x.bettor_2),
+                    _ => Err(Error::BettorDoesNotExist),
+                },
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        // --------------------------------------------------------
+        // Offer-related functions
+        // --------------------------------------------------------
+        /// (For makers): Post an open offer that any number of takers can fill in part, sending
+        /// the creation fee and your whole stake.
+        ///   Takers stake up to `stake_bettor_2` in total, and each fill matches a share of your
+        ///   stake at the same odds. Every fill becomes a bet of its own between you and the
+        ///   taker, on the terms given here (see `create_bet`). The fees are quoted on the whole
+        ///   offer when it is posted, and takers share the acceptance fee pro rata to their
+        ///   stakes, whatever the fee model is by the time they fill. Fills have to take at
+        ///   least the minimum fill share of `stake_bettor_2`, unless they take the rest.
+        #[ink(message, payable)]
+        #[allow(clippy::too_many_arguments)]
+        pub fn post_offer(
+            &mut self,
+            stake_bettor_1: Balance,
+            stake_bettor_2: Balance,
+            criteria_for_winning: String,
+            event_decided_by: String,
+            commit_reveal: bool,
+            referrer: Option<AccountId>,
+            token: Option<AccountId>,
+            outcomes: Vec<String>,
+            backed_outcomes: Vec<u8>,
+        ) -> Result<u32, Error> {
+            if stake_bettor_1 == 0 || stake_bettor_2 == 0 {
+                return Err(Error::InvalidOfferSize);
+            }
+            let offer_number = self.number_of_offers;
+            let number_of_offers = offer_number.checked_add(1).ok_or(Error::Overflow)?;
+            let terms = self.propose_bet(
+                stake_bettor_1,
+                stake_bettor_2,
+                None,
+                criteria_for_winning,
+                event_decided_by,
+                commit_reveal,
+                referrer,
+                token,
+                &outcomes,
+                backed_outcomes,
+            )?;
+            self.collect_stake_of_bettor_1(&terms)?;
+            for (outcome, label) in outcomes.iter().enumerate() {
+                self.offer_outcome_labels
+                    .insert((offer_number, outcome as u8), label);
+            }
+
+            let acceptance_fee = terms.acceptance_fee;
+            let offer = Offer {
+                terms,
+                remaining_stake_bettor_1: stake_bettor_1,
+                remaining_stake_bettor_2: stake_bettor_2,
+                remaining_acceptance_fee: acceptance_fee,
+                min_fill: Self::basis_points_of(stake_bettor_2, self.min_fill_basis_points),
+                number_of_fills: 0,
+                state: OfferState::Open,
+            };
+            self.offers.insert(offer_number, &offer);
+            self.number_of_offers = number_of_offers;
+            self.debug_assert_solvent();
+            Ok(offer_number)
+        }
+
+        /// (For takers): Fill part of an open offer by staking `stake` against it, sending
+        /// your stake and your share of the acceptance fee (see `get_offer_acceptance_fee`).
+        ///   The fill becomes an accepted bet between the maker and you, which settles on its
+        ///   own. Only the fill that takes the rest of the offer can stake less than the
+        ///   offer's minimum fill, and it matches all of the maker's remaining stake.
+        ///   `backed_outcomes` works as for `accept_bet`.
+        ///   Returns the number of the bet the fill created.
+        #[ink(message, payable)]
+        pub fn fill_offer(
+            &mut self,
+            n: u32,
+            stake: Balance,
+            backed_outcomes: Vec<u8>,
+        ) -> Result<u32, Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let mut offer = match self.offers.get(n) {
+                Some(x) => x,
+                None => return Err(Error::OfferDoesNotExist),
+            };
+            if offer.state != OfferState::Open {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if offer.terms.bettor_1 == Some(caller) {
+                return Err(Error::CallerNotValidBettor);
+            }
+            if stake == 0 || stake > offer.remaining_stake_bettor_2 {
+                return Err(Error::InvalidOfferSize);
+            }
+            // otherwise takers could split an offer into bets too small to be worth settling
+            if stake < offer.min_fill && stake != offer.remaining_stake_bettor_2 {
+                return Err(Error::FillTooSmall);
+            }
+            let matched_stake = if stake == offer.remaining_stake_bettor_2 {
+                offer.remaining_stake_bettor_1
+            } else {
+                Self::pro_rata(
+                    stake,
+                    offer.terms.stake_bettor_1,
+                    offer.terms.stake_bettor_2,
+                )
+            };
+            if matched_stake == 0 {
+                return Err(Error::InvalidOfferSize);
+            }
+            let backed_outcomes = Self::backing_of_bettor_2(&offer.terms, backed_outcomes)?;
+            let fee = Self::acceptance_fee_of_fill(&offer, stake);
+            let amount_due = stake.checked_add(fee).ok_or(Error::Overflow)?;
+            let native_due = match offer.terms.token {
+                None => amount_due,
+                Some(_) => 0,
+            };
+            if transferred_amount < native_due {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            let next_bet = self.latest_bet.checked_add(1).ok_or(Error::Overflow)?;
+            let number_of_fills = offer
+                .number_of_fills
+                .checked_add(1)
+                .ok_or(Error::Overflow)?;
+
+            let mut bet = offer.terms.clone();
+            bet.stake_bettor_1 = matched_stake;
+            bet.stake_bettor_2 = stake;
+            // the maker paid their fee on the whole offer
+            bet.bet_creation_fee_paid = 0;
+            bet.acceptance_fee = fee;
+            bet.bettor_2 = Some(caller);
+            bet.offer = Some(n);
+            bet.outcomes_backed_by_bettor_2 = backed_outcomes;
+            Self::set_state(&mut bet, BetState::BetAcceptedByBettor2)?;
+            let (maker, token, referrer) = (bet.bettor_1.unwrap(), bet.token, bet.referrer);
+            self.receive_tokens(token, caller, amount_due)?;
+
+            let bet_number = self.latest_bet;
+            self.latest_bet = next_bet;
+            self.bets.insert(bet_number, &bet);
+            self.offer_fills
+                .insert((n, offer.number_of_fills), &bet_number);
+            offer.remaining_stake_bettor_1 -= matched_stake;
+            offer.remaining_stake_bettor_2 -= stake;
+            offer.remaining_acceptance_fee -= fee;
+            offer.number_of_fills = number_of_fills;
+            if offer.remaining_stake_bettor_2 == 0 {
+                offer.state = OfferState::Filled;
+            }
+            self.offers.insert(n, &offer);
+            let now = self.env().block_timestamp();
+            self.last_bet_between
+                .insert(Self::account_pair(maker, caller), &now);
+            // the maker's matched stake was already escrowed with the offer
+            self.escrow(token, stake)?;
+            self.distribute_fee_in(token, fee, referrer)?;
+            self.credit(caller, transferred_amount - native_due)?;
+            self.debug_assert_solvent();
+            Ok(bet_number)
+        }
+
+        /// A fill's share of the acceptance fee quoted when the offer was posted. The fill that
+        /// takes the rest of the offer pays what is left of it.
+        fn acceptance_fee_of_fill(offer: &Offer, stake: Balance) -> Balance {
+            if stake == offer.remaining_stake_bettor_2 {
+                offer.remaining_acceptance_fee
+            } else {
+                Self::pro_rata(
+                    stake,
+                    offer.terms.acceptance_fee,
+                    offer.terms.stake_bettor_2,
+                )
+            }
+        }
+
+        /// (For makers): Cancel what is left of an open offer, getting back the stake no fill
+        /// has matched. The bets already filled are not affected.
+        #[ink(message)]
+        pub fn cancel_offer(&mut self, n: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let mut offer = match self.offers.get(n) {
+                Some(x) => x,
+                None => return Err(Error::OfferDoesNotExist),
+            };
+            if offer.terms.bettor_1 != Some(caller) {
+                return Err(Error::NotBettor1);
+            }
+            if offer.state != OfferState::Open {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+
+            let (token, refund) = (offer.terms.token, offer.remaining_stake_bettor_1);
+            offer.remaining_stake_bettor_1 = 0;
+            offer.remaining_stake_bettor_2 = 0;
+            offer.state = OfferState::Cancelled;
+            self.offers.insert(n, &offer);
+            self.release_escrow(token, refund);
+            self.credit_in(token, caller, refund)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// List up to `limit` open offers as (offer number, maker stake left, taker stake
+        /// left), starting the search at offer `start`
+        #[ink(message)]
+        pub fn list_open_offers(&self, start: u32, limit: u32) -> Vec<(u32, Balance, Balance)> {
+            (start..self.number_of_offers)
+                .filter_map(|n| self.offers.get(n).map(|offer| (n, offer)))
+                .filter(|(_, offer)| offer.state == OfferState::Open)
+                .take(limit as usize)
+                .map(|(n, offer)| {
+                    (
+                        n,
+                        offer.remaining_stake_bettor_1,
+                        offer.remaining_stake_bettor_2,
+                    )
+                })
+                .collect()
+        }
+
+        /// Get how many offers have been posted
+        #[ink(message)]
+        pub fn get_number_of_offers(&self) -> u32 {
+            self.number_of_offers
+        }
+
+        /// Get how much of the maker's stake is left to match on an offer, and how much takers
+        /// can still stake against it
+        #[ink(message)]
+        pub fn get_offer_remaining(&self, n: u32) -> Result<(Balance, Balance), Error> {
+            match self.offers.get(n) {
+                Some(x) => Ok((x.remaining_stake_bettor_1, x.remaining_stake_bettor_2)),
+                None => Err(Error::OfferDoesNotExist),
+            }
+        }
+
+        /// Get what a taker has to pay on top of `stake` to fill an offer
+        #[ink(message)]
+        pub fn get_offer_acceptance_fee(&self, n: u32, stake: Balance) -> Result<Balance, Error> {
+            let offer = match self.offers.get(n) {
+                Some(x) => x,
+                None => return Err(Error::OfferDoesNotExist),
+            };
+            if stake == 0 || stake > offer.remaining_stake_bettor_2 {
+                return Err(Error::InvalidOfferSize);
+            }
+            Ok(Self::acceptance_fee_of_fill(&offer, stake))
+        }
+
+        /// Get offer state
+        #[ink(message)]
+        pub fn get_offer_state(&self, n: u32) -> Result<OfferState, Error> {
+            match self.offers.get(n) {
+                Some(x) => Ok(x.state),
+                None => Err(Error::OfferDoesNotExist),
+            }
+        }
+
+        /// Get the numbers of up to `limit` bets created by filling an offer, starting at its
+        /// fill `start`
+        #[ink(message)]
+        pub fn get_offer_fills(&self, n: u32, start: u32, limit: u32) -> Result<Vec<u32>, Error> {
+            let offer = match self.offers.get(n) {
+                Some(x) => x,
+                None => return Err(Error::OfferDoesNotExist),
+            };
+            Ok((start..offer.number_of_fills)
+                .take(limit as usize)
+                .filter_map(|fill| self.offer_fills.get((n, fill)))
+                .collect())
+        }
+
+        /// (For the final decision maker): Set the smallest share of new offers (in basis
+        /// points) a fill can take, unless it takes the rest
+        #[ink(message)]
+        pub fn set_offer_config(&mut self, min_fill_basis_points: u16) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if min_fill_basis_points as u128 > BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.min_fill_basis_points = min_fill_basis_points;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the smallest share of new offers (in basis points) a fill can take
+        #[ink(message)]
+        pub fn get_offer_config(&self) -> u16 {
+            self.min_fill_basis_points
+        }
+
+        // --------------------------------------------------------
+        // Dispute-related functions
+        // --------------------------------------------------------
+        /// (For bettors): Have pseudo-randomly selected reviewers assigned to a bet the bettors
+        /// disagree on, paying the dispute fee.
+        ///   Bets where either bettor stakes at least the jury threshold get a jury panel, the
+        ///   others a single reviewer.
+        #[ink(message, payable)]
+        pub fn assign_reviewer(&mut self, n: u32) -> Result<Vec<AccountId>, Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let ruling_deadline = self.deadline_after(self.ruling_period)?;
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            if bet.bettor_1 != Some(caller) && bet.bettor_2 != Some(caller) {
+                return Err(Error::CallerNotValidBettor);
+            }
+            if bet.state != BetState::BettorsDisagree {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            let panel_size = if bet.stake_bettor_1.max(bet.stake_bettor_2) >= self.jury_threshold {
+                self.jury_size as u32
+            } else {
+                1
+            };
+            if transferred_amount < self.dispute_fee {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+
+            let panel = self.select_reviewers(&bet, panel_size);
+            if panel.len() < panel_size as usize {
+                return Err(Error::NoEligibleReviewer);
+            }
+            let dispute_fee = self.dispute_fee;
+            self.total_escrowed = self
+                .total_escrowed
+                .checked_add(dispute_fee)
+                .ok_or(Error::Overflow)?;
+            self.credit(caller, transferred_amount - dispute_fee)?;
+            bet.reviewers = panel.clone();
+            bet.reviewer_votes = panel.iter().map(|_| None).collect();
+            bet.dispute_requested_by = Some(caller);
+            bet.dispute_fee_paid = dispute_fee;
+            bet.ruling_deadline = Some(ruling_deadline);
+            Self::set_state(&mut bet, BetState::ReviewerAssigned)?;
+            self.bets.insert(n, &bet);
+            for reviewer in panel.iter() {
+                self.open_case(*reviewer)?;
+                self.update_reviewer_stats(*reviewer, |stats| {
+                    stats.cases_assigned = stats.cases_assigned.saturating_add(1)
+                });
+            }
+            self.debug_assert_solvent();
+            Ok(panel)
+        }
+
+        /// (For reviewers): Vote on the outcome of a bet the bettors disagree on.
+        ///   Once every reviewer has voted, the outcome more than half of them voted for is the
+        ///   ruling. Without such a majority, or with an `Undecideable` majority, both bettors
+        ///   are refunded. The reviewers in the majority share the dispute fee.
+        #[ink(message)]
+        pub fn submit_reviewer_outcome(
+            &mut self,
+            n: u32,
+            outcome: BetOutcome,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let seat = match bet.reviewers.iter().position(|x| *x == caller) {
+                Some(x) => x,
+                None => return Err(Error::NotAssignedReviewer),
+            };
+            if bet.state != BetState::ReviewerAssigned {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if bet.reviewer_votes[seat].is_some() {
+                return Err(Error::AlreadyVoted);
+            }
+
+            bet.reviewer_votes[seat] = Some(outcome);
+            self.close_case(caller);
+            if bet.reviewer_votes.iter().any(|vote| vote.is_none()) {
+                self.bets.insert(n, &bet);
+                self.debug_assert_solvent();
+                return Ok(());
+            }
+
+            let ruling =
+                Self::panel_majority(&bet.reviewer_votes).unwrap_or(BetOutcome::Undecideable);
+            bet.outcome_claimed_by_reviewer = Some(ruling);
+            bet.reviewer_ruled_at = Some(now);
+            Self::set_state(&mut bet, Self::state_for_ruling(ruling))?;
+            self.bets.insert(n, &bet);
+            let (fee, requested_by) = (bet.dispute_fee_paid, bet.dispute_requested_by.unwrap());
+            let majority = Self::majority_reviewers(&bet);
+            self.reward_majority(fee, requested_by, &majority)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For reviewers): Recuse yourself from a bet you have not voted on yet.
+        ///   Another eligible reviewer takes your seat on the panel.
+        #[ink(message)]
+        pub fn recuse_from_case(&mut self, n: u32) -> Result<AccountId, Error> {
+            let caller = self.env().caller();
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let seat = match bet.reviewers.iter().position(|x| *x == caller) {
+                Some(x) => x,
+                None => return Err(Error::NotAssignedReviewer),
+            };
+            if bet.state != BetState::ReviewerAssigned {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if bet.reviewer_votes[seat].is_some() {
+                return Err(Error::CannotRecuseAfterVoting);
+            }
+
+            let replacement = match self.select_reviewers(&bet, 1).first() {
+                Some(x) => *x,
+                None => return Err(Error::NoEligibleReviewer),
+            };
+            bet.reviewers[seat] = replacement;
+            bet.recused_reviewers.push(caller);
+            self.bets.insert(n, &bet);
+            self.close_case(caller);
+            self.open_case(replacement)?;
+            self.update_reviewer_stats(replacement, |stats| {
+                stats.cases_assigned = stats.cases_assigned.saturating_add(1)
+            });
+            self.debug_assert_solvent();
+            Ok(replacement)
+        }
+
+        /// (For bettors): Attach evidence to a bet the bettors disagree on, until the evidence
+        /// deadline and up to the per-bettor limit.
+        ///   `uri` points to the evidence or briefly describes it, and `content_hash` lets
+        ///   reviewers check it has not changed since.
+        #[ink(message)]
+        pub fn submit_evidence(
+            &mut self,
+            n: u32,
+            content_hash: Hash,
+            uri: String,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            let max_evidence_per_bettor = self.max_evidence_per_bettor;
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let index = bet.evidence_submitted_by_bettor_1 as u32
+                + bet.evidence_submitted_by_bettor_2 as u32;
+            let submitted = if bet.bettor_1 == Some(caller) {
+                &mut bet.evidence_submitted_by_bettor_1
+            } else if bet.bettor_2 == Some(caller) {
+                &mut bet.evidence_submitted_by_bettor_2
+            } else {
+                return Err(Error::CallerNotValidBettor);
+            };
+            let evidence_deadline = match (bet.state, bet.evidence_deadline) {
+                (
+                    BetState::BettorsDisagree
+                    | BetState::ReviewerAssigned
+                    | BetState::DisputeEscalated
+                    | BetState::RulingAppealed,
+                    Some(x),
+                ) => x,
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            };
+            if now > evidence_deadline {
+                return Err(Error::EvidenceDeadlinePassed);
+            }
+            if uri.len() > MAX_EVIDENCE_URI_LENGTH {
+                return Err(Error::EvidenceUriTooLong);
+            }
+            if *submitted >= max_evidence_per_bettor {
+                return Err(Error::EvidenceLimitReached);
+            }
+
+            *submitted += 1;
+            self.bets.insert(n, &bet);
+            let evidence = Evidence {
+                submitted_by: caller,
+                content_hash,
+                uri,
+                submitted_at: now,
+            };
+            self.evidence.insert((n, index), &evidence);
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the evidence the bettors submitted for a bet
+        #[ink(message)]
+        pub fn get_evidence(&self, n: u32) -> Result<Vec<Evidence>, Error> {
+            let bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let submitted = bet.evidence_submitted_by_bettor_1 as u32
+                + bet.evidence_submitted_by_bettor_2 as u32;
+            Ok((0..submitted)
+                .filter_map(|index| self.evidence.get((n, index)))
+                .collect())
+        }
+
+        /// Get until when the bettors can submit evidence for a bet
+        #[ink(message)]
+        pub fn get_evidence_deadline(&self, n: u32) -> Result<Option<Timestamp>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.evidence_deadline),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// (For the final decision maker): Set how long (in milliseconds) bettors have to submit
+        /// evidence once they disagree, and how many entries each of them can submit
+        #[ink(message)]
+        pub fn set_evidence_config(
+            &mut self,
+            evidence_period: Timestamp,
+            max_evidence_per_bettor: u8,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            self.evidence_period = evidence_period;
+            self.max_evidence_per_bettor = max_evidence_per_bettor;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the evidence period (in milliseconds) and the per-bettor evidence limit
+        #[ink(message)]
+        pub fn get_evidence_config(&self) -> (Timestamp, u8) {
+            (self.evidence_period, self.max_evidence_per_bettor)
+        }
+
+        /// (For anyone): Replace the reviewers who did not vote by the ruling deadline, recording
+        /// a missed deadline against each of them.
+        ///   Once the dispute has been reassigned the maximum number of times, or there is
+        ///   nobody left to take over, it escalates to the final decision maker instead.
+        ///   Returns the replacement reviewers, which is empty if the dispute escalated.
+        #[ink(message)]
+        pub fn reassign_overdue_reviewers(&mut self, n: u32) -> Result<Vec<AccountId>, Error> {
+            let now = self.env().block_timestamp();
+            let ruling_deadline = self.deadline_after(self.ruling_period)?;
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            if bet.state != BetState::ReviewerAssigned {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            match bet.ruling_deadline {
+                Some(deadline) if now > deadline => {}
+                _ => return Err(Error::RulingDeadlineNotPassed),
+            }
+            let overdue_seats: Vec<usize> = bet
+                .reviewer_votes
+                .iter()
+                .enumerate()
+                .filter(|(_, vote)| vote.is_none())
+                .map(|(seat, _)| seat)
+                .collect();
+
+            let replacements = if bet.reassignments >= self.max_reassignments {
+                Vec::new()
+            } else {
+                self.select_reviewers(&bet, overdue_seats.len() as u32)
+            };
+            let escalate = replacements.len() < overdue_seats.len();
+
+            let overdue: Vec<AccountId> = overdue_seats
+                .iter()
+                .map(|seat| bet.reviewers[*seat])
+                .collect();
+            for reviewer in overdue.iter() {
+                self.close_case(*reviewer);
+                self.update_reviewer_stats(*reviewer, |stats| {
+                    stats.missed_deadlines = stats.missed_deadlines.saturating_add(1)
+                });
+            }
+
+            bet.overdue_reviewers.extend(overdue);
+            if escalate {
+                bet.ruling_deadline = None;
+                Self::set_state(&mut bet, BetState::DisputeEscalated)?;
+                self.bets.insert(n, &bet);
+                self.debug_assert_solvent();
+                return Ok(Vec::new());
+            }
+
+            for (seat, replacement) in overdue_seats.iter().zip(replacements.iter()) {
+                bet.reviewers[*seat] = *replacement;
+            }
+            bet.reassignments = bet.reassignments.checked_add(1).ok_or(Error::Overflow)?;
+            bet.ruling_deadline = Some(ruling_deadline);
+            self.bets.insert(n, &bet);
+            for replacement in replacements.iter() {
+                self.open_case(*replacement)?;
+                self.update_reviewer_stats(*replacement, |stats| {
+                    stats.cases_assigned = stats.cases_assigned.saturating_add(1)
+                });
+            }
+            self.debug_assert_solvent();
+            Ok(replacements)
+        }
+
+        /// (For bettors): Appeal the reviewer's ruling to the final decision maker.
+        ///   Only the losing bettor may appeal, and only within the appeal period.
+        ///   The appeal bond is refunded if the final ruling leaves the appellant better off
+        ///   and forfeited otherwise.
+        #[ink(message, payable)]
+        pub fn appeal_ruling(&mut self, n: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let now = self.env().block_timestamp();
+            let appeal_period = self.appeal_period;
+            let appeal_bond = self.appeal_bond;
+
+            if transferred_amount < appeal_bond {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            let ruled_at = match (bet.outcome_claimed_by_reviewer, bet.reviewer_ruled_at) {
+                (Some(_), Some(ruled_at)) => ruled_at,
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            };
+            if bet.appellant.is_some() {
+                return Err(Error::AlreadyAppealed);
+            }
+            let losing_bettor = match bet.state {
+                BetState::Bettor1Wins => caller == bet.bettor_2.unwrap(),
+                BetState::Bettor2Wins => caller == bet.bettor_1.unwrap(),
+                BetState::BettorsDrew => {
+                    caller == bet.bettor_1.unwrap() || caller == bet.bettor_2.unwrap()
+                }
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            };
+            if !losing_bettor {
+                return Err(Error::NotLosingBettor);
+            }
+            if now > ruled_at.saturating_add(appeal_period) {
+                return Err(Error::AppealWindowClosed);
+            }
+
+            bet.appellant = Some(caller);
+            bet.appeal_bond_paid = appeal_bond;
+            Self::set_state(&mut bet, BetState::RulingAppealed)?;
+            self.bets.insert(n, &bet);
+            // the majority's bonds stay at stake until the appeal is decided
+            for reviewer in Self::majority_reviewers(&bet) {
+                self.open_case(reviewer)?;
+            }
+            self.total_escrowed = self
+                .total_escrowed
+                .checked_add(appeal_bond)
+                .ok_or(Error::Overflow)?;
+            self.credit(caller, transferred_amount - appeal_bond)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For the final decision maker): Issue a binding ruling on an appealed or escalated bet.
+        ///   Overturning the reviewer's ruling slashes the reviewer, with the slashed amount going
+        ///   to the bettor the final ruling favors, and refunds the appeal bond if the appellant
+        ///   is better off for it.
+        ///   Ruling on an escalated bet refunds the dispute fee, since no reviewer ruled.
+        #[ink(message)]
+        pub fn submit_final_outcome(&mut self, n: u32, outcome: BetOutcome) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+
+            let mut bet = match self.bets.get(n) {
+                Some(x) => x,
+                None => return Err(Error::BetDoesNotExist),
+            };
+            if bet.state == BetState::DisputeEscalated {
+                bet.outcome_decided_by_final_decision_maker = Some(outcome);
+                Self::set_state(&mut bet, Self::state_for_ruling(outcome))?;
+                self.bets.insert(n, &bet);
+                let (fee, requested_by) = (bet.dispute_fee_paid, bet.dispute_requested_by.unwrap());
+                self.total_escrowed -= fee;
+                self.credit(requested_by, fee)?;
+                self.debug_assert_solvent();
+                return Ok(());
+            }
+            if bet.state != BetState::RulingAppealed {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+
+            let reviewer_state = Self::state_for_ruling(bet.outcome_claimed_by_reviewer.unwrap());
+            let final_state = Self::state_for_ruling(outcome);
+            bet.outcome_decided_by_final_decision_maker = Some(outcome);
+            Self::set_state(&mut bet, final_state)?;
+            self.bets.insert(n, &bet);
+
+            let (appellant, bond) = (bet.appellant.unwrap(), bet.appeal_bond_paid);
+            let better_off = Self::standing_of(&bet, appellant, final_state)
+                > Self::standing_of(&bet, appellant, reviewer_state);
+            // overturning a win to a draw favors the loser, who is the appellant
+            let favored = match final_state {
+                BetState::Bettor1Wins => bet.bettor_1.unwrap(),
+                BetState::Bettor2Wins => bet.bettor_2.unwrap(),
+                _ => appellant,
+            };
+            let majority = Self::majority_reviewers(&bet);
+            self.settle_appeal(
+                &majority,
+                (appellant, Some(favored)),
+                bond,
+                final_state != reviewer_state,
+                better_off,
+            )?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// How a ruling leaves a bettor: 2 if they win, 1 on a draw and 0 if they lose
+        fn standing_of(bet: &Bet, bettor: AccountId, state: BetState) -> u8 {
+            match state {
+                BetState::Bettor1Wins if Some(bettor) == bet.bettor_1 => 2,
+                BetState::Bettor2Wins if Some(bettor) == bet.bettor_2 => 2,
+                BetState::Bettor1Wins | BetState::Bettor2Wins => 0,
+                _ => 1,
+            }
+        }
+
+        /// Settle an appeal the final decision maker ruled on, closing the majority's cases.
+        ///   If the ruling was `overturned` the majority are slashed, with the slashed amount
+        ///   going to the `favored` party, or to the treasury if there is none. The appeal bond
+        ///   is refunded only if the appellant is `better_off` for the final ruling, and
+        ///   forfeited otherwise.
+        fn settle_appeal(
+            &mut self,
+            majority: &[AccountId],
+            (appellant, favored): (AccountId, Option<AccountId>),
+            bond: Balance,
+            overturned: bool,
+            better_off: bool,
+        ) -> Result<(), Error> {
+            self.total_escrowed -= bond;
+            for reviewer in majority.iter() {
+                self.close_case(*reviewer);
+            }
+
+            let mut slashed = 0;
+            for reviewer in majority {
+                if !overturned {
+                    self.update_reviewer_stats(*reviewer, |stats| {
+                        stats.rulings_upheld = stats.rulings_upheld.saturating_add(1)
+                    });
+                    continue;
+                }
+                self.update_reviewer_stats(*reviewer, |stats| {
+                    stats.rulings_overturned = stats.rulings_overturned.saturating_add(1)
+                });
+                slashed = self
+                    .slash_reviewer(*reviewer)?
+                    .checked_add(slashed)
+                    .ok_or(Error::Overflow)?;
+            }
+
+            if better_off {
+                self.credit(appellant, bond)?;
+            } else {
+                // the appellant forfeits the appeal bond
+                self.treasury_balance = self
+                    .treasury_balance
+                    .checked_add(bond)
+                    .ok_or(Error::Overflow)?;
+            }
+            match favored {
+                Some(favored) => self.credit(favored, slashed),
+                None => {
+                    self.treasury_balance = self
+                        .treasury_balance
+                        .checked_add(slashed)
+                        .ok_or(Error::Overflow)?;
+                    Ok(())
+                }
+            }
+        }
+
+        /// (For the final decision maker): Set how long and how much it takes to appeal a ruling
+        #[ink(message)]
+        pub fn set_appeal_config(
+            &mut self,
+            appeal_period: Timestamp,
+            appeal_bond: Balance,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if self.unbonding_period <= appeal_period {
+                return Err(Error::UnbondingPeriodTooShort);
+            }
+            self.appeal_period = appeal_period;
+            self.appeal_bond = appeal_bond;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the appeal period (in milliseconds) and appeal bond
+        #[ink(message)]
+        pub fn get_appeal_config(&self) -> (Timestamp, Balance) {
+            (self.appeal_period, self.appeal_bond)
+        }
+
+        /// Get the bettor who appealed the reviewer's ruling
+        #[ink(message)]
+        pub fn get_appellant(&self, n: u32) -> Result<Option<AccountId>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.appellant),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get the outcome the final decision maker ruled on
+        #[ink(message)]
+        pub fn get_outcome_decided_by_final_decision_maker(
+            &self,
+            n: u32,
+        ) -> Result<Option<BetOutcome>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.outcome_decided_by_final_decision_maker),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// (For the final decision maker): Set the dispute fee, the amount wagered from which
+        /// disputes go to a jury panel, and how many reviewers sit on a panel
+        #[ink(message)]
+        pub fn set_dispute_config(
+            &mut self,
+            dispute_fee: Balance,
+            jury_threshold: Balance,
+            jury_size: u8,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if jury_size.is_multiple_of(2) {
+                return Err(Error::JurySizeMustBeOdd);
+            }
+            self.dispute_fee = dispute_fee;
+            self.jury_threshold = jury_threshold;
+            self.jury_size = jury_size;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the dispute fee, jury threshold and jury size
+        #[ink(message)]
+        pub fn get_dispute_config(&self) -> (Balance, Balance, u8) {
+            (self.dispute_fee, self.jury_threshold, self.jury_size)
+        }
+
+        /// (For the final decision maker): Set how long (in milliseconds) reviewers have to rule
+        /// and how many times a dispute is reassigned before it escalates
+        #[ink(message)]
+        pub fn set_ruling_deadline_config(
+            &mut self,
+            ruling_period: Timestamp,
+            max_reassignments: u8,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            self.ruling_period = ruling_period;
+            self.max_reassignments = max_reassignments;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the ruling period (in milliseconds) and the maximum number of reassignments
+        #[ink(message)]
+        pub fn get_ruling_deadline_config(&self) -> (Timestamp, u8) {
+            (self.ruling_period, self.max_reassignments)
+        }
+
+        /// Get until when the reviewers can rule on a bet before they can be replaced
+        #[ink(message)]
+        pub fn get_ruling_deadline(&self, n: u32) -> Result<Option<Timestamp>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.ruling_deadline),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get the reviewers replaced for missing the ruling deadline on a bet
+        #[ink(message)]
+        pub fn get_overdue_reviewers(&self, n: u32) -> Result<Vec<AccountId>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.overdue_reviewers.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// (For the final decision maker): Set the conflict-of-interest rules for reviewers:
+        /// how long (in milliseconds) having bet against either bettor keeps them from
+        /// reviewing a bet, with zero turning the rule off, and whether a bet's referrer may
+        /// review it. Bettors can never review their own bets.
+        #[ink(message)]
+        pub fn set_conflict_of_interest_config(
+            &mut self,
+            recent_opponent_period: Timestamp,
+            exclude_referrer: bool,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            self.recent_opponent_period = recent_opponent_period;
+            self.exclude_referrer = exclude_referrer;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the recent opponent period (in milliseconds) and whether referrers are excluded
+        #[ink(message)]
+        pub fn get_conflict_of_interest_config(&self) -> (Timestamp, bool) {
+            (self.recent_opponent_period, self.exclude_referrer)
+        }
+
+        /// Get when two accounts last bet against each other
+        #[ink(message)]
+        pub fn get_last_bet_between(&self, a: AccountId, b: AccountId) -> Option<Timestamp> {
+            self.last_bet_between.get(Self::account_pair(a, b))
+        }
+
+        /// Get the reviewers who recused themselves from a bet
+        #[ink(message)]
+        pub fn get_recused_reviewers(&self, n: u32) -> Result<Vec<AccountId>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.recused_reviewers.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Pick up to `count` reviewers who may review `bet`
+        fn select_reviewers(&mut self, bet: &Bet, count: u32) -> Vec<AccountId> {
+            self.select_eligible_reviewers(count, |this, reviewer| {
+                this.is_eligible_reviewer(bet, reviewer)
+            })
+        }
+
+        /// Pick up to `count` registered reviewers that `is_eligible` accepts, starting the
+        /// search at a pseudo-random index
+        fn select_eligible_reviewers(
+            &mut self,
+            count: u32,
+            is_eligible: impl Fn(&Self, AccountId) -> bool,
+        ) -> Vec<AccountId> {
+            if self.number_of_reviewers == 0 {
+                return Vec::new();
+            }
+            let start = self.pseudo_random_index(self.number_of_reviewers);
+            (0..self.number_of_reviewers)
+                .map(|offset| {
+                    // both are below `number_of_reviewers`, so their sum fits in a u64
+                    ((start as u64 + offset as u64) % self.number_of_reviewers as u64) as u32
+                })
+                .filter_map(|index| self.reviewers.get(index))
+                .filter(|reviewer| is_eligible(self, *reviewer))
+                .take(count as usize)
+                .collect()
+        }
+
+        /// Whether `reviewer` may review the bet
+        fn is_eligible_reviewer(&self, bet: &Bet, reviewer: AccountId) -> bool {
+            !bet.reviewers.contains(&reviewer)
+                && !bet.recused_reviewers.contains(&reviewer)
+                && !bet.overdue_reviewers.contains(&reviewer)
+                && !self.has_conflict_of_interest(bet, reviewer)
+                && self.get_reputation_score(reviewer) >= self.min_reputation_score
+        }
+
+        /// Whether `reviewer` is a party to the bet, referred it, or recently bet against
+        /// either bettor
+        fn has_conflict_of_interest(&self, bet: &Bet, reviewer: AccountId) -> bool {
+            if bet.bettor_1 == Some(reviewer) || bet.bettor_2 == Some(reviewer) {
+                return true;
+            }
+            if self.exclude_referrer && bet.referrer == Some(reviewer) {
+                return true;
+            }
+            self.has_recently_bet_against(reviewer, bet.bettor_1)
+                || self.has_recently_bet_against(reviewer, bet.bettor_2)
+        }
+
+        /// Whether `reviewer` bet against `bettor` within the recent opponent period
+        fn has_recently_bet_against(&self, reviewer: AccountId, bettor: Option<AccountId>) -> bool {
+            let bettor = match bettor {
+                Some(x) => x,
+                None => return false,
+            };
+            if self.recent_opponent_period == 0 {
+                return false;
+            }
+            match self
+                .last_bet_between
+                .get(Self::account_pair(reviewer, bettor))
+            {
+                Some(bet_at) => {
+                    self.env().block_timestamp()
+                        <= bet_at.saturating_add(self.recent_opponent_period)
+                }
+                None => false,
+            }
+        }
+
+        /// The key of a pair of accounts in `last_bet_between`, whichever order they come in
+        fn account_pair(a: AccountId, b: AccountId) -> (AccountId, AccountId) {
+            if a < b {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        }
+
+        /// The outcome more than half of the jury panel voted for, if any
+        fn panel_majority<T: Copy + PartialEq>(votes: &[Option<T>]) -> Option<T> {
+            votes.iter().flatten().copied().find(|outcome| {
+                let count = votes.iter().filter(|vote| **vote == Some(*outcome)).count();
+                count * 2 > votes.len()
+            })
+        }
+
+        /// The reviewers who voted with the panel's majority, and are answerable for its ruling
+        fn majority_reviewers(bet: &Bet) -> Vec<AccountId> {
+            Self::majority_of(&bet.reviewers, &bet.reviewer_votes)
+        }
+
+        /// The reviewers on a panel who voted for its majority outcome
+        fn majority_of<T: Copy + PartialEq>(
+            reviewers: &[AccountId],
+            votes: &[Option<T>],
+        ) -> Vec<AccountId> {
+            let majority = match Self::panel_majority(votes) {
+                Some(x) => x,
+                None => return Vec::new(),
+            };
+            reviewers
+                .iter()
+                .zip(votes.iter())
+                .filter(|(_, vote)| **vote == Some(majority))
+                .map(|(reviewer, _)| *reviewer)
+                .collect()
+        }
+
+        /// Split the dispute fee and the dispute reward from the reviewer reward pool among the
+        /// majority, with any remainder going to the first of them.
+        /// Without a majority the fee goes back to the bettor who paid it and nobody is rewarded.
+        fn reward_majority(
+            &mut self,
+            fee: Balance,
+            requested_by: AccountId,
+            majority: &[AccountId],
+        ) -> Result<(), Error> {
+            if majority.is_empty() {
+                self.total_escrowed -= fee;
+                return self.credit(requested_by, fee);
+            }
+
+            let reward = self.dispute_reward.min(self.reviewer_reward_pool);
+            let total = fee.checked_add(reward).ok_or(Error::Overflow)?;
+            self.total_escrowed -= fee;
+            self.reviewer_reward_pool -= reward;
+            // the shares add up to `total`, so none of these can overflow
+            let share = total / majority.len() as u128;
+            let remainder = total - share * majority.len() as u128;
+            for (i, reviewer) in majority.iter().enumerate() {
+                let amount = if i == 0 { share + remainder } else { share };
+                self.credit(*reviewer, amount)?;
+            }
+            Ok(())
+        }
+
+        /// The state a bet settles into given a reviewer's or final decision maker's ruling
+        fn state_for_ruling(outcome: BetOutcome) -> BetState {
+            match outcome {
+                BetOutcome::Bettor1Wins => BetState::Bettor1Wins,
+                BetOutcome::Bettor2Wins => BetState::Bettor2Wins,
+                BetOutcome::Draw | BetOutcome::Undecideable => BetState::BettorsDrew,
+            }
+        }
+
+        /// Whether a reviewer's ruling on the bet can still be appealed
+        fn is_appealable(&self, bet: &Bet) -> bool {
+            match (bet.reviewer_ruled_at, bet.appellant) {
+                (Some(ruled_at), None) => {
+                    self.env().block_timestamp() <= ruled_at.saturating_add(self.appeal_period)
+                }
+                _ => false,
+            }
+        }
+
+        /// Get the reviewers assigned to a bet
+        #[ink(message)]
+        pub fn get_reviewers(&self, n: u32) -> Result<Vec<AccountId>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.reviewers.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get how each reviewer assigned to a bet voted so far
+        #[ink(message)]
+        pub fn get_reviewer_votes(&self, n: u32) -> Result<Vec<Option<BetOutcome>>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.reviewer_votes.clone()),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        /// Get the outcome the reviewer ruled on
+        #[ink(message)]
+        pub fn get_outcome_claimed_by_reviewer(&self, n: u32) -> Result<Option<BetOutcome>, Error> {
+            match self.bets.get(n) {
+                Some(x) => Ok(x.outcome_claimed_by_reviewer),
+                None => Err(Error::BetDoesNotExist),
+            }
+        }
+
+        // --------------------------------------------------------
+        // Pool-related functions
+        // --------------------------------------------------------
+        /// (For anyone): Create a parimutuel pool on `number_of_outcomes` outcomes, which anyone
+        /// can stake on until `betting_closes_at`.
+        ///   Once the outcome is final, the backers of the winning outcome split the pool in
+        ///   proportion to their stakes, after the pool rake. What rounding leaves over goes to
+        ///   the dust recipient. If the outcome is undecideable or nobody backed it, every
+        ///   stake is refunded without a rake.
+        ///   With a PSP22 `token`, stakes are pulled against an allowance and paid out in the
+        ///   token. Dispute fees and appeal bonds are paid in the native currency either way.
+        ///   The pool creation fee is split like a bet creation fee, and anything sent beyond it
+        ///   is credited back.
+        #[ink(message, payable)]
+        pub fn create_pool(
+            &mut self,
+            criteria_for_winning: String,
+            number_of_outcomes: u8,
+            betting_closes_at: String,
+            token: Option<AccountId>,
+        ) -> Result<u32, Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            if !(2..=MAX_OUTCOMES).contains(&number_of_outcomes) {
+                return Err(Error::InvalidNumberOfOutcomes);
+            }
+            if criteria_for_winning.len() > MAX_CRITERIA_LENGTH {
+                return Err(Error::CriteriaTooLong);
+            }
+            let betting_closes_at = match betting_closes_at.as_str().parse::<DateTime<Utc>>() {
+                Ok(y) => y.timestamp_millis().max(0) as Timestamp,
+                Err(_) => return Err(Error::NotDatetimeString),
+            };
+            let fee = self.pool_creation_fee;
+            if transferred_amount < fee {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            let pool_number = self.number_of_pools;
+            let number_of_pools = pool_number.checked_add(1).ok_or(Error::Overflow)?;
+
+            self.distribute_fee(fee, None)?;
+            self.credit(caller, transferred_amount - fee)?;
+            let pool = Pool {
+                creator: caller,
+                criteria_for_winning,
+                betting_closes_at,
+                resolution_deadline: betting_closes_at.saturating_add(self.pool_resolution_period),
+                token,
+                rake_basis_points: self.pool_rake_basis_points,
+                dust_recipient: self.pool_dust_recipient,
+                stakes_per_outcome: ink::prelude::vec![0; number_of_outcomes as usize],
+                total_staked: 0,
+                reviewers: Vec::new(),
+                reviewer_votes: Vec::new(),
+                ruling_deadline: None,
+                overdue_reviewers: Vec::new(),
+                reassignments: 0,
+                resolution_requested_by: None,
+                dispute_fee_paid: 0,
+                outcome_ruled_by_reviewers: None,
+                reviewer_ruled_at: None,
+                appellant: None,
+                appeal_bond_paid: 0,
+                outcome_decided_by_final_decision_maker: None,
+                paying_stake: 0,
+                payout: 0,
+                unclaimed_stake: 0,
+                unpaid_payout: 0,
+                state: PoolState::Open,
+            };
+            self.pools.insert(pool_number, &pool);
+            self.number_of_pools = number_of_pools;
+            self.debug_assert_solvent();
+            Ok(pool_number)
+        }
+
+        /// (For backers): Stake `amount` on the outcome with index `outcome` before the pool's
+        /// cutoff, sending it along.
+        ///   Anything sent beyond the stake is credited back. Backers can stake more than once
+        ///   and on several outcomes.
+        #[ink(message, payable)]
+        pub fn stake_on_outcome(
+            &mut self,
+            n: u32,
+            outcome: u8,
+            amount: Balance,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let now = self.env().block_timestamp();
+            // an empty stake would make the caller a backer, who can neither review the pool
+            // nor has anything at stake
+            if amount == 0 {
+                return Err(Error::InvalidStake);
+            }
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            if pool.state != PoolState::Open || now >= pool.betting_closes_at {
+                return Err(Error::PoolClosed);
+            }
+            let outcome = outcome as usize;
+            if outcome >= pool.stakes_per_outcome.len() {
+                return Err(Error::OutcomeDoesNotExist);
+            }
+            let token = pool.token;
+            let native_due = match token {
+                None => amount,
+                Some(_) => 0,
+            };
+            if transferred_amount < native_due {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            let staked_on_outcome = pool.stakes_per_outcome[outcome]
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            let total_staked = pool
+                .total_staked
+                .checked_add(amount)
+                .ok_or(Error::Overflow)?;
+            let mut stakes = self
+                .pool_stakes
+                .get((n, caller))
+                .unwrap_or_else(|| ink::prelude::vec![0; pool.stakes_per_outcome.len()]);
+            // a backer's stake is part of the outcome's, so it cannot overflow either
+            stakes[outcome] += amount;
+
+            self.receive_tokens(token, caller, amount)?;
+            self.escrow(token, amount)?;
+            self.credit(caller, transferred_amount - native_due)?;
+            self.pool_stakes.insert((n, caller), &stakes);
+            pool.stakes_per_outcome[outcome] = staked_on_outcome;
+            pool.total_staked = total_staked;
+            self.pools.insert(n, &pool);
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For backers): Have pseudo-randomly selected reviewers rule on the outcome of a pool
+        /// past its cutoff, paying the dispute fee.
+        ///   Pools holding at least the jury threshold get a jury panel, the others a single
+        ///   reviewer. The pool's creator and backers cannot be selected, and if not enough
+        ///   reviewers are left, the pool escalates to the final decision maker instead.
+        ///   Returns the reviewers selected, which is empty if the pool escalated.
+        #[ink(message, payable)]
+        pub fn request_pool_resolution(&mut self, n: u32) -> Result<Vec<AccountId>, Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let now = self.env().block_timestamp();
+            let ruling_deadline = self.deadline_after(self.ruling_period)?;
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            if !self.pool_stakes.contains((n, caller)) {
+                return Err(Error::NotBacker);
+            }
+            if pool.state != PoolState::Open {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if now < pool.betting_closes_at {
+                return Err(Error::PoolStillOpen);
+            }
+            let panel_size = if pool.total_staked >= self.jury_threshold {
+                self.jury_size as u32
+            } else {
+                1
+            };
+            if transferred_amount < self.dispute_fee {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            let dispute_fee = self.dispute_fee;
+            let total_escrowed = self
+                .total_escrowed
+                .checked_add(dispute_fee)
+                .ok_or(Error::Overflow)?;
+
+            let mut panel = self.select_pool_reviewers(n, &pool, panel_size);
+            pool.resolution_requested_by = Some(caller);
+            pool.dispute_fee_paid = dispute_fee;
+            if panel.len() < panel_size as usize {
+                // nobody else can rule on the pool, so the stakes are not left locked
+                panel.clear();
+                Self::set_pool_state(&mut pool, PoolState::DisputeEscalated)?;
+            } else {
+                pool.reviewers = panel.clone();
+                pool.reviewer_votes = panel.iter().map(|_| None).collect();
+                pool.ruling_deadline = Some(ruling_deadline);
+                Self::set_pool_state(&mut pool, PoolState::ReviewerAssigned)?;
+            }
+            self.total_escrowed = total_escrowed;
+            self.credit(caller, transferred_amount - dispute_fee)?;
+            self.pools.insert(n, &pool);
+            for reviewer in panel.iter() {
+                self.open_case(*reviewer)?;
+                self.update_reviewer_stats(*reviewer, |stats| {
+                    stats.cases_assigned = stats.cases_assigned.saturating_add(1)
+                });
+            }
+            self.debug_assert_solvent();
+            Ok(panel)
+        }
+
+        /// (For reviewers): Vote on the outcome of a pool.
+        ///   Once every reviewer has voted, the outcome more than half of them voted for is the
+        ///   ruling, and without such a majority the pool is undecideable. The reviewers in the
+        ///   majority share the dispute fee.
+        #[ink(message)]
+        pub fn submit_pool_ruling(&mut self, n: u32, outcome: PoolOutcome) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let now = self.env().block_timestamp();
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            let seat = match pool.reviewers.iter().position(|x| *x == caller) {
+                Some(x) => x,
+                None => return Err(Error::NotAssignedReviewer),
+            };
+            if pool.state != PoolState::ReviewerAssigned {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            Self::check_pool_outcome(&pool, outcome)?;
+            if pool.reviewer_votes[seat].is_some() {
+                return Err(Error::AlreadyVoted);
+            }
+
+            pool.reviewer_votes[seat] = Some(outcome);
+            self.close_case(caller);
+            if pool.reviewer_votes.iter().any(|vote| vote.is_none()) {
+                self.pools.insert(n, &pool);
+                self.debug_assert_solvent();
+                return Ok(());
+            }
+
+            let ruling =
+                Self::panel_majority(&pool.reviewer_votes).unwrap_or(PoolOutcome::Undecideable);
+            pool.outcome_ruled_by_reviewers = Some(ruling);
+            pool.reviewer_ruled_at = Some(now);
+            Self::set_pool_state(&mut pool, PoolState::Resolved)?;
+            let (fee, requested_by) =
+                (pool.dispute_fee_paid, pool.resolution_requested_by.unwrap());
+            let majority = Self::majority_of(&pool.reviewers, &pool.reviewer_votes);
+            self.pools.insert(n, &pool);
+            self.reward_majority(fee, requested_by, &majority)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For anyone): Replace the reviewers who did not rule on a pool by the ruling deadline,
+        /// recording a missed deadline against each of them.
+        ///   Once the pool has been reassigned the maximum number of times, or there is nobody
+        ///   left to take over, it escalates to the final decision maker instead.
+        ///   Returns the replacement reviewers, which is empty if the pool escalated.
+        #[ink(message)]
+        pub fn reassign_overdue_pool_reviewers(&mut self, n: u32) -> Result<Vec<AccountId>, Error> {
+            let now = self.env().block_timestamp();
+            let ruling_deadline = self.deadline_after(self.ruling_period)?;
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            if pool.state != PoolState::ReviewerAssigned {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            match pool.ruling_deadline {
+                Some(deadline) if now > deadline => {}
+                _ => return Err(Error::RulingDeadlineNotPassed),
+            }
+            let overdue_seats: Vec<usize> = pool
+                .reviewer_votes
+                .iter()
+                .enumerate()
+                .filter(|(_, vote)| vote.is_none())
+                .map(|(seat, _)| seat)
+                .collect();
+
+            let replacements = if pool.reassignments >= self.max_reassignments {
+                Vec::new()
+            } else {
+                self.select_pool_reviewers(n, &pool, overdue_seats.len() as u32)
+            };
+            let escalate = replacements.len() < overdue_seats.len();
+
+            let overdue: Vec<AccountId> = overdue_seats
+                .iter()
+                .map(|seat| pool.reviewers[*seat])
+                .collect();
+            for reviewer in overdue.iter() {
+                self.close_case(*reviewer);
+                self.update_reviewer_stats(*reviewer, |stats| {
+                    stats.missed_deadlines = stats.missed_deadlines.saturating_add(1)
+                });
+            }
+
+            pool.overdue_reviewers.extend(overdue);
+            if escalate {
+                pool.ruling_deadline = None;
+                Self::set_pool_state(&mut pool, PoolState::DisputeEscalated)?;
+                self.pools.insert(n, &pool);
+                self.debug_assert_solvent();
+                return Ok(Vec::new());
+            }
+
+            for (seat, replacement) in overdue_seats.iter().zip(replacements.iter()) {
+                pool.reviewers[*seat] = *replacement;
+            }
+            pool.reassignments = pool.reassignments.checked_add(1).ok_or(Error::Overflow)?;
+            pool.ruling_deadline = Some(ruling_deadline);
+            self.pools.insert(n, &pool);
+            for replacement in replacements.iter() {
+                self.open_case(*replacement)?;
+                self.update_reviewer_stats(*replacement, |stats| {
+                    stats.cases_assigned = stats.cases_assigned.saturating_add(1)
+                });
+            }
+            self.debug_assert_solvent();
+            Ok(replacements)
+        }
+
+        /// (For backers): Appeal the reviewers' ruling on a pool to the final decision maker.
+        ///   Only backers with a stake on an outcome the ruling did not pick may appeal, and only
+        ///   within the appeal period. The appeal bond is refunded if the final ruling leaves the
+        ///   appellant with a larger share and forfeited otherwise.
+        #[ink(message, payable)]
+        pub fn appeal_pool_ruling(&mut self, n: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let transferred_amount = self.env().transferred_value();
+            let now = self.env().block_timestamp();
+            let appeal_period = self.appeal_period;
+            let appeal_bond = self.appeal_bond;
+
+            if transferred_amount < appeal_bond {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            let stakes = match self.pool_stakes.get((n, caller)) {
+                Some(x) => x,
+                None => return Err(Error::NotBacker),
+            };
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            if pool.appellant.is_some() {
+                return Err(Error::AlreadyAppealed);
+            }
+            let (ruling, ruled_at) = match (
+                pool.state,
+                pool.outcome_ruled_by_reviewers,
+                pool.reviewer_ruled_at,
+            ) {
+                (PoolState::Resolved, Some(ruling), Some(ruled_at)) => (ruling, ruled_at),
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            };
+            let lost = stakes
+                .iter()
+                .enumerate()
+                .any(|(outcome, stake)| *stake > 0 && ruling != PoolOutcome::Winner(outcome as u8));
+            if !lost {
+                return Err(Error::NotLosingBettor);
+            }
+            if now > ruled_at.saturating_add(appeal_period) {
+                return Err(Error::AppealWindowClosed);
+            }
+
+            pool.appellant = Some(caller);
+            pool.appeal_bond_paid = appeal_bond;
+            Self::set_pool_state(&mut pool, PoolState::RulingAppealed)?;
+            self.pools.insert(n, &pool);
+            // the majority's bonds stay at stake until the appeal is decided
+            for reviewer in Self::majority_of(&pool.reviewers, &pool.reviewer_votes) {
+                self.open_case(reviewer)?;
+            }
+            self.total_escrowed = self
+                .total_escrowed
+                .checked_add(appeal_bond)
+                .ok_or(Error::Overflow)?;
+            self.credit(caller, transferred_amount - appeal_bond)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For the final decision maker): Issue a binding ruling on an appealed or escalated
+        /// pool, settling the appeal or refunding the dispute fee as for bets
+        #[ink(message)]
+        pub fn submit_final_pool_outcome(
+            &mut self,
+            n: u32,
+            outcome: PoolOutcome,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            Self::check_pool_outcome(&pool, outcome)?;
+            match pool.state {
+                PoolState::DisputeEscalated => {
+                    pool.outcome_decided_by_final_decision_maker = Some(outcome);
+                    Self::set_pool_state(&mut pool, PoolState::Resolved)?;
+                    let (fee, requested_by) =
+                        (pool.dispute_fee_paid, pool.resolution_requested_by.unwrap());
+                    self.pools.insert(n, &pool);
+                    self.total_escrowed -= fee;
+                    self.credit(requested_by, fee)?;
+                }
+                PoolState::RulingAppealed => {
+                    pool.outcome_decided_by_final_decision_maker = Some(outcome);
+                    Self::set_pool_state(&mut pool, PoolState::Resolved)?;
+                    let ruling = pool.outcome_ruled_by_reviewers.unwrap();
+                    let (appellant, bond) = (pool.appellant.unwrap(), pool.appeal_bond_paid);
+                    let stakes = self.pool_stakes.get((n, appellant)).unwrap();
+                    let better_off = Self::pool_share_of(&pool, &stakes, outcome)
+                        > Self::pool_share_of(&pool, &stakes, ruling);
+                    let majority = Self::majority_of(&pool.reviewers, &pool.reviewer_votes);
+                    self.pools.insert(n, &pool);
+                    // an overturn that does not pay the appellant more favors other backers,
+                    // who cannot be singled out, so the slashed bonds go to the treasury
+                    let favored = if better_off { Some(appellant) } else { None };
+                    self.settle_appeal(
+                        &majority,
+                        (appellant, favored),
+                        bond,
+                        ruling != outcome,
+                        better_off,
+                    )?;
+                }
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            }
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// (For backers): Claim your share of a pool whose outcome is final, crediting it for
+        /// you to `claim` in the pool's currency.
+        ///   The first claim takes the rake, and the last winning claim pays the rounding dust
+        ///   to the dust recipient. Returns how much was credited, which is nothing for stakes
+        ///   on losing outcomes.
+        #[ink(message)]
+        pub fn claim_pool_payout(&mut self, n: u32) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            let stakes = match self.pool_stakes.get((n, caller)) {
+                Some(x) => x,
+                None => return Err(Error::NotBacker),
+            };
+            match pool.state {
+                PoolState::Resolved if self.is_pool_appealable(&pool) => {
+                    return Err(Error::AppealWindowOpen)
+                }
+                PoolState::Resolved => self.start_pool_payout(&mut pool)?,
+                PoolState::PayingOut => {}
+                _ => return Err(Error::InvalidStateForCallingFunction),
+            }
+
+            let stake = match Self::paying_outcome(&pool) {
+                Some(outcome) => stakes[outcome as usize],
+                // each stake is part of the pool's total, so their sum fits in a balance
+                None => stakes.iter().sum(),
+            };
+            let share = if stake == 0 {
+                0
+            } else {
+                Self::pro_rata(stake, pool.payout, pool.paying_stake)
+            };
+            let (token, dust_recipient) = (pool.token, pool.dust_recipient);
+            let unclaimed_stake = pool.unclaimed_stake - stake;
+            let mut unpaid_payout = pool.unpaid_payout - share;
+            let dust = if unclaimed_stake == 0 {
+                unpaid_payout
+            } else {
+                0
+            };
+            unpaid_payout -= dust;
+
+            self.pool_stakes.remove((n, caller));
+            self.release_escrow(token, share + dust);
+            self.credit_in(token, caller, share)?;
+            self.credit_in(token, dust_recipient, dust)?;
+            pool.unclaimed_stake = unclaimed_stake;
+            pool.unpaid_payout = unpaid_payout;
+            self.pools.insert(n, &pool);
+            self.debug_assert_solvent();
+            Ok(share)
+        }
+
+        /// Fix what a pool pays out once its outcome is final, taking the rake unless every
+        /// stake is refunded. A reviewers' ruling nobody appealed in time stands once the pool
+        /// pays out.
+        fn start_pool_payout(&mut self, pool: &mut Pool) -> Result<(), Error> {
+            let (paying_stake, rake) = match Self::paying_outcome(pool) {
+                Some(outcome) => (
+                    pool.stakes_per_outcome[outcome as usize],
+                    Self::basis_points_of(pool.total_staked, pool.rake_basis_points),
+                ),
+                None => (pool.total_staked, 0),
+            };
+            let token = pool.token;
+            let payout = pool.total_staked - rake;
+            let unappealed_ruling = pool.reviewer_ruled_at.is_some() && pool.appellant.is_none();
+            let majority = Self::majority_of(&pool.reviewers, &pool.reviewer_votes);
+
+            self.release_escrow(token, rake);
+            self.distribute_fee_in(token, rake, None)?;
+            pool.paying_stake = paying_stake;
+            pool.payout = payout;
+            pool.unclaimed_stake = paying_stake;
+            pool.unpaid_payout = payout;
+            Self::set_pool_state(pool, PoolState::PayingOut)?;
+            if unappealed_ruling {
+                for reviewer in majority {
+                    self.update_reviewer_stats(reviewer, |stats| {
+                        stats.rulings_upheld = stats.rulings_upheld.saturating_add(1)
+                    });
+                }
+            }
+            Ok(())
+        }
+
+        /// (For anyone): Resolve a pool nobody asked to have resolved by its resolution
+        /// deadline without a ruling, so that every stake is refunded.
+        #[ink(message)]
+        pub fn expire_pool(&mut self, n: u32) -> Result<(), Error> {
+            let now = self.env().block_timestamp();
+            let mut pool = match self.pools.get(n) {
+                Some(x) => x,
+                None => return Err(Error::PoolDoesNotExist),
+            };
+            if pool.state != PoolState::Open {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            if now <= pool.resolution_deadline {
+                return Err(Error::PoolNotExpired);
+            }
+
+            Self::set_pool_state(&mut pool, PoolState::Resolved)?;
+            self.pools.insert(n, &pool);
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Whether a pool may move from state `from` to state `to`
+        fn is_valid_pool_transition(from: PoolState, to: PoolState) -> bool {
+            use PoolState::*;
+            match from {
+                // a pool nobody can rule on escalates, and one nobody resolves expires
+                Open => matches!(to, ReviewerAssigned | DisputeEscalated | Resolved),
+                ReviewerAssigned => matches!(to, Resolved | DisputeEscalated),
+                DisputeEscalated | RulingAppealed => to == Resolved,
+                Resolved => matches!(to, RulingAppealed | PayingOut),
+                PayingOut => false,
+            }
+        }
+
+        /// Move a pool to `state`, as long as the transition table allows it
+        fn set_pool_state(pool: &mut Pool, state: PoolState) -> Result<(), Error> {
+            if !Self::is_valid_pool_transition(pool.state, state) {
+                return Err(Error::InvalidStateForCallingFunction);
+            }
+            pool.state = state;
+            Ok(())
+        }
+
+        /// The outcome whose backers split a pool, or `None` if every stake is refunded
+        /// because the outcome is undecideable or nobody backed it
+        fn paying_outcome(pool: &Pool) -> Option<u8> {
+            match Self::final_pool_outcome(pool) {
+                Some(PoolOutcome::Winner(outcome))
+                    if pool.stakes_per_outcome[outcome as usize] > 0 =>
+                {
+                    Some(outcome)
+                }
+                _ => None,
+            }
+        }
+
+        /// What a backer with `stakes` would be paid if the pool were resolved with `outcome`
+        fn pool_share_of(pool: &Pool, stakes: &[Balance], outcome: PoolOutcome) -> Balance {
+            match outcome {
+                PoolOutcome::Winner(x) if pool.stakes_per_outcome[x as usize] > 0 => {
+                    let rake = Self::basis_points_of(pool.total_staked, pool.rake_basis_points);
+                    Self::pro_rata(
+                        stakes[x as usize],
+                        pool.total_staked - rake,
+                        pool.stakes_per_outcome[x as usize],
+                    )
+                }
+                // each stake is part of the pool's total, so their sum fits in a balance
+                _ => stakes.iter().sum(),
+            }
+        }
+
+        /// The final decision maker's ruling on a pool if there is one, otherwise the reviewers'
+        fn final_pool_outcome(pool: &Pool) -> Option<PoolOutcome> {
+            pool.outcome_decided_by_final_decision_maker
+                .or(pool.outcome_ruled_by_reviewers)
+        }
+
+        /// Check that a ruling names one of the pool's outcomes
+        fn check_pool_outcome(pool: &Pool, outcome: PoolOutcome) -> Result<(), Error> {
+            match outcome {
+                PoolOutcome::Winner(x) if x as usize >= pool.stakes_per_outcome.len() => {
+                    Err(Error::OutcomeDoesNotExist)
+                }
+                _ => Ok(()),
+            }
+        }
+
+        /// Whether the reviewers' ruling on a pool can still be appealed
+        fn is_pool_appealable(&self, pool: &Pool) -> bool {
+            match (pool.reviewer_ruled_at, pool.appellant) {
+                (Some(ruled_at), None) => {
+                    self.env().block_timestamp() <= ruled_at.saturating_add(self.appeal_period)
+                }
+                _ => false,
+            }
+        }
+
+        /// Pick up to `count` reviewers who may rule on `pool`, the pool numbered `n`
+        fn select_pool_reviewers(&mut self, n: u32, pool: &Pool, count: u32) -> Vec<AccountId> {
+            self.select_eligible_reviewers(count, |this, reviewer| {
+                !pool.reviewers.contains(&reviewer)
+                    && !pool.overdue_reviewers.contains(&reviewer)
+                    && pool.creator != reviewer
+                    && !this.pool_stakes.contains((n, reviewer))
+                    && this.get_reputation_score(reviewer) >= this.min_reputation_score
+            })
+        }
+
+        /// (For the final decision maker): Set the rake (in basis points), the dust recipient,
+        /// the creation fee and the resolution period (in milliseconds after the cutoff) of
+        /// new pools
+        #[ink(message)]
+        pub fn set_pool_config(
+            &mut self,
+            rake_basis_points: u16,
+            dust_recipient: AccountId,
+            creation_fee: Balance,
+            resolution_period: Timestamp,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if rake_basis_points as u128 > BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            self.pool_rake_basis_points = rake_basis_points;
+            self.pool_dust_recipient = dust_recipient;
+            self.pool_creation_fee = creation_fee;
+            self.pool_resolution_period = resolution_period;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the rake (in basis points), the dust recipient, the creation fee and the
+        /// resolution period of new pools
+        #[ink(message)]
+        pub fn get_pool_config(&self) -> (u16, AccountId, Balance, Timestamp) {
+            (
+                self.pool_rake_basis_points,
+                self.pool_dust_recipient,
+                self.pool_creation_fee,
+                self.pool_resolution_period,
+            )
+        }
+
+        /// Get how many pools have been created
+        #[ink(message)]
+        pub fn get_number_of_pools(&self) -> u32 {
+            self.number_of_pools
+        }
+
+        /// Get pool state
+        #[ink(message)]
+        pub fn get_pool_state(&self, n: u32) -> Result<PoolState, Error> {
+            match self.pools.get(n) {
+                Some(x) => Ok(x.state),
+                None => Err(Error::PoolDoesNotExist),
+            }
+        }
+
+        /// Get how much is staked on each outcome of a pool
+        #[ink(message)]
+        pub fn get_pool_stakes(&self, n: u32) -> Result<Vec<Balance>, Error> {
+            match self.pools.get(n) {
+                Some(x) => Ok(x.stakes_per_outcome),
+                None => Err(Error::PoolDoesNotExist),
+            }
+        }
+
+        /// Get how much a backer staked on each outcome of a pool. This is empty once they
+        /// have claimed their payout.
+        #[ink(message)]
+        pub fn get_backer_stakes(&self, n: u32, backer: AccountId) -> Vec<Balance> {
+            self.pool_stakes.get((n, backer)).unwrap_or_default()
+        }
+
+        /// Get the final outcome of a pool, or the reviewers' ruling while it can be appealed
+        #[ink(message)]
+        pub fn get_pool_outcome(&self, n: u32) -> Result<Option<PoolOutcome>, Error> {
+            match self.pools.get(n) {
+                Some(x) => Ok(Self::final_pool_outcome(&x)),
+                None => Err(Error::PoolDoesNotExist),
+            }
+        }
+
+        /// Get the reviewers assigned to a pool
+        #[ink(message)]
+        pub fn get_pool_reviewers(&self, n: u32) -> Result<Vec<AccountId>, Error> {
+            match self.pools.get(n) {
+                Some(x) => Ok(x.reviewers),
+                None => Err(Error::PoolDoesNotExist),
+            }
+        }
+
+        // --------------------------------------------------------
+        // Reputation-related functions
+        // --------------------------------------------------------
+        /// Register as a reviewer by locking the reviewer bond.
+        ///   A reviewer who is still unbonding can re-register, topping up their bond if needed.
+        ///   Anything sent beyond what the bond is short of is credited back to the sender.
+        #[ink(message, payable)]
+        pub fn register_as_reviewer(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if self.reviewer_indices.contains(caller) {
+                return Err(Error::AlreadyRegisteredReviewer);
+            }
+            let transferred_amount = self.env().transferred_value();
+            let bond = self.reviewer_bonds.get(caller).unwrap_or_default();
+            let top_up = self.reviewer_bond.saturating_sub(bond);
+            if transferred_amount < top_up {
+                return Err(Error::InssufficientAmountOfTokensSent);
+            }
+            let total_reviewer_bonds = self
+                .total_reviewer_bonds
+                .checked_add(top_up)
+                .ok_or(Error::Overflow)?;
+            // `top_up` is at most the reviewer bond, so the sum is at most the larger of the two
+            let bond = bond + top_up;
+            let index = self.number_of_reviewers;
+            let number_of_reviewers = index.checked_add(1).ok_or(Error::Overflow)?;
+
+            self.reviewer_bonds.insert(caller, &bond);
+            self.total_reviewer_bonds = total_reviewer_bonds;
+            self.unbonding_until.remove(caller);
+
+            self.reviewers.insert(index, &caller);
+            self.reviewer_indices.insert(caller, &index);
+            self.number_of_reviewers = number_of_reviewers;
+            self.credit(caller, transferred_amount - top_up)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Stop being a reviewer. The last registered reviewer takes over the freed index.
+        ///   The bond can be withdrawn once the unbonding period has passed.
+        #[ink(message)]
+        pub fn unregister_as_reviewer(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if !self.reviewer_indices.contains(caller) {
+                return Err(Error::NotRegisteredReviewer);
+            }
+            if self.open_cases.get(caller).unwrap_or_default() > 0 {
+                return Err(Error::ReviewerHasOpenCases);
+            }
+            self.deregister_reviewer(caller)?;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Remove a registered reviewer and start unbonding their bond
+        fn deregister_reviewer(&mut self, reviewer: AccountId) -> Result<(), Error> {
+            let unlocks_at = self.deadline_after(self.unbonding_period)?;
+            let index = self.reviewer_indices.take(reviewer).unwrap();
+
+            let last_index = self.number_of_reviewers - 1;
+            let last_reviewer = self.reviewers.take(last_index).unwrap();
+            if index != last_index {
+                self.reviewers.insert(index, &last_reviewer);
+                self.reviewer_indices.insert(last_reviewer, &index);
+            }
+            self.number_of_reviewers = last_index;
+
+            self.unbonding_until.insert(reviewer, &unlocks_at);
+            Ok(())
+        }
+
+        /// Withdraw the bond of an unregistered reviewer after the unbonding period
+        #[ink(message)]
+        pub fn withdraw_reviewer_bond(&mut self) -> Result<Balance, Error> {
+            let caller = self.env().caller();
+            let unlocks_at = match self.unbonding_until.get(caller) {
+                Some(x) => x,
+                None => return Err(Error::NotUnbonding),
+            };
+            if self.env().block_timestamp() < unlocks_at {
+                return Err(Error::BondStillLocked);
+            }
+            if self.open_cases.get(caller).unwrap_or_default() > 0 {
+                return Err(Error::ReviewerHasOpenCases);
+            }
+
+            let bond = self.reviewer_bonds.take(caller).unwrap_or_default();
+            self.total_reviewer_bonds -= bond;
+            self.unbonding_until.remove(caller);
+            if self.env().transfer(caller, bond).is_err() {
+                return Err(Error::TransferFailed);
+            }
+            self.debug_assert_solvent();
+            Ok(bond)
+        }
+
+        /// (For the final decision maker): Set the reviewer bond, the share of it slashed
+        /// for an overturned ruling (in basis points) and the unbonding period (in milliseconds),
+        /// which has to be longer than the appeal period
+        #[ink(message)]
+        pub fn set_reviewer_bond_config(
+            &mut self,
+            reviewer_bond: Balance,
+            slash_basis_points: u16,
+            unbonding_period: Timestamp,
+        ) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if slash_basis_points as u128 > BASIS_POINTS {
+                return Err(Error::InvalidBasisPoints);
+            }
+            if unbonding_period <= self.appeal_period {
+                return Err(Error::UnbondingPeriodTooShort);
+            }
+            self.reviewer_bond = reviewer_bond;
+            self.slash_basis_points = slash_basis_points;
+            self.unbonding_period = unbonding_period;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the reviewer bond, slash share (in basis points) and unbonding period (in milliseconds)
+        #[ink(message)]
+        pub fn get_reviewer_bond_config(&self) -> (Balance, u16, Timestamp) {
+            (
+                self.reviewer_bond,
+                self.slash_basis_points,
+                self.unbonding_period,
+            )
+        }
+
+        /// Get how much a reviewer has locked
+        #[ink(message)]
+        pub fn get_reviewer_bond(&self, reviewer: AccountId) -> Balance {
+            self.reviewer_bonds.get(reviewer).unwrap_or_default()
+        }
+
+        /// Get how many open cases a reviewer has
+        #[ink(message)]
+        pub fn get_open_cases(&self, reviewer: AccountId) -> u32 {
+            self.open_cases.get(reviewer).unwrap_or_default()
+        }
+
+        fn open_case(&mut self, reviewer: AccountId) -> Result<(), Error> {
+            let cases = self.open_cases.get(reviewer).unwrap_or_default();
+            let cases = cases.checked_add(1).ok_or(Error::Overflow)?;
+            self.open_cases.insert(reviewer, &cases);
+            Ok(())
+        }
+
+        fn close_case(&mut self, reviewer: AccountId) {
+            let cases = self.open_cases.get(reviewer).unwrap_or_default();
+            self.open_cases.insert(reviewer, &cases.saturating_sub(1));
+        }
+
+        /// Take the slash share out of a reviewer's bond and return how much was taken.
+        ///   A registered reviewer left with less than the reviewer bond is deregistered, and
+        ///   has to register again with a topped-up bond to be assigned new cases.
+        fn slash_reviewer(&mut self, reviewer: AccountId) -> Result<Balance, Error> {
+            let bond = self.reviewer_bonds.get(reviewer).unwrap_or_default();
+            let slashed = Self::basis_points_of(bond, self.slash_basis_points);
+            self.reviewer_bonds.insert(reviewer, &(bond - slashed));
+            self.total_reviewer_bonds -= slashed;
+            if bond - slashed < self.reviewer_bond && self.reviewer_indices.contains(reviewer) {
+                self.deregister_reviewer(reviewer)?;
+            }
+            Ok(slashed)
+        }
+
+        #[ink(message)]
+        pub fn is_registered_as_reviewer(&self) -> Result<bool, ()> {
+            Ok(self.reviewer_indices.contains(self.env().caller()))
+        }
+
+        /// Get how many registered reviewers there are
+        #[ink(message)]
+        pub fn get_number_of_reviewers(&self) -> u32 {
+            self.number_of_reviewers
+        }
+
+        /// Get the registered reviewer at `index`
+        #[ink(message)]
+        pub fn get_reviewer_at(&self, index: u32) -> Option<AccountId> {
+            self.reviewers.get(index)
+        }
+
+        /// List up to `limit` registered reviewers, starting at index `start`
+        #[ink(message)]
+        pub fn list_reviewers(&self, start: u32, limit: u32) -> Vec<AccountId> {
+            let end = start.saturating_add(limit).min(self.number_of_reviewers);
+            (start..end)
+                .filter_map(|index| self.reviewers.get(index))
+                .collect()
+        }
+
+        /// Get how a reviewer has performed so far
+        #[ink(message)]
+        pub fn get_reviewer_stats(&self, reviewer: AccountId) -> ReviewerStats {
+            self.reviewer_stats.get(reviewer).unwrap_or_default()
+        }
+
+        /// Get a reviewer's reputation score, from 0 to 100.
+        ///   It is the share of judged rulings that were upheld, where an overturned ruling
+        ///   and a missed deadline both count against the reviewer.
+        ///   Reviewers without any judged rulings start with the maximum score.
+        #[ink(message)]
+        pub fn get_reputation_score(&self, reviewer: AccountId) -> u8 {
+            let stats = self.get_reviewer_stats(reviewer);
+            let judged = stats.rulings_upheld as u64
+                + stats.rulings_overturned as u64
+                + stats.missed_deadlines as u64;
+            if judged == 0 {
+                return MAX_REPUTATION_SCORE;
+            }
+            (stats.rulings_upheld as u64 * MAX_REPUTATION_SCORE as u64 / judged) as u8
+        }
+
+        /// (For the final decision maker): Stop assigning disputes to reviewers with a lower score
+        #[ink(message)]
+        pub fn set_min_reputation_score(&mut self, score: u8) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if score > MAX_REPUTATION_SCORE {
+                return Err(Error::InvalidReputationScore);
+            }
+            self.min_reputation_score = score;
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get the reputation score below which reviewers are not assigned to disputes
+        #[ink(message)]
+        pub fn get_min_reputation_score(&self) -> u8 {
+            self.min_reputation_score
+        }
+
+        /// Stats are only informational, so their counts saturate rather than fail the call
+        fn update_reviewer_stats(
+            &mut self,
+            reviewer: AccountId,
+            update: impl FnOnce(&mut ReviewerStats),
+        ) {
+            let mut stats = self.get_reviewer_stats(reviewer);
+            update(&mut stats);
+            self.reviewer_stats.insert(reviewer, &stats);
+        }
+
+        /// (For the final decision maker): Allow or stop an account from settling bets on the
+        /// bettors' behalf
+        #[ink(message)]
+        pub fn set_keeper(&mut self, account: AccountId, is_keeper: bool) -> Result<(), Error> {
+            self.only_final_decision_maker()?;
+            if is_keeper {
+                self.keepers.insert(account, &true);
+            } else {
+                self.keepers.remove(account);
+            }
+            self.debug_assert_solvent();
+            Ok(())
+        }
+
+        /// Get whether an account can settle bets on the bettors' behalf
+        #[ink(message)]
+        pub fn is_keeper(&self, account: AccountId) -> bool {
+            self.keepers.get(account).unwrap_or(false)
+        }
+
+        #[ink(message)]
+        pub fn is_final_decision_maker(&self) -> Result<bool, ()> {
+            Ok(self.final_decision_maker == self.env().caller())
+        }
+    }
+
+    /// Calls to PSP22 token contracts, with the selectors from the PSP22 standard.
+    ///   The off-chain environment cannot call other contracts, so unit tests run these
+    ///   against mock tokens instead.
+    mod psp22 {
+        use super::{AccountId, Balance, Error, PSP22Error};
+        use ink::env::call::{ExecutionInput, Selector};
+        use ink::prelude::vec::Vec;
+
+        /// What calling a token returns: the call can fail, the message can fail to dispatch,
+        /// and the message returns `R`
+        pub type CallResult<R> = Result<ink::MessageResult<R>, ink::env::Error>;
+
+        /// How calls reach token contracts. The contract is given one when it is constructed.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, scale::Encode, scale::Decode)]
+        #[cfg_attr(
+            feature = "std",
+            derive(::scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+        )]
+        pub enum Transport {
+            /// Cross-contract calls to the token contract
+            CrossContractCall,
+            /// The mock tokens deployed in the current test
+            #[cfg(test)]
+            Mock,
+        }
+
+        impl Transport {
+            /// Call the message `input` names on `token` and decode what it returns
+            fn invoke<Args: scale::Encode, R: scale::Decode>(
+                self,
+                token: AccountId,
+                input: ExecutionInput<Args>,
+            ) -> CallResult<R> {
+                match self {
+                    Transport::CrossContractCall => {
+                        ink::env::call::build_call::<super::Environment>()
+                            .call(token)
+                            .exec_input(input)
+                            .returns::<R>()
+                            .try_invoke()
+                    }
+                    #[cfg(test)]
+                    Transport::Mock => super::tests::invoke_mock_token(token, input),
+                }
+            }
+        }
+
+        /// Move `value` of `token` from the contract to `to`
+        pub fn transfer(
+            transport: Transport,
+            token: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), Error> {
+            let input = ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer")))
+                .push_arg(to)
+                .push_arg(value)
+                .push_arg(Vec::<u8>::new());
+            transferred(transport.invoke(token, input))
+        }
+
+        /// Move `value` of `token` from `from` to `to`, spending the contract's allowance
+        pub fn transfer_from(
+            transport: Transport,
+            token: AccountId,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), Error> {
+            let input =
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::transfer_from")))
+                    .push_arg(from)
+                    .push_arg(to)
+                    .push_arg(value)
+                    .push_arg(Vec::<u8>::new());
+            transferred(transport.invoke(token, input))
+        }
+
+        /// How much of `token` `owner` holds, or `None` if the token could not be asked
+        pub fn balance_of(
+            transport: Transport,
+            token: AccountId,
+            owner: AccountId,
+        ) -> Option<Balance> {
+            let input =
+                ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22::balance_of")))
+                    .push_arg(owner);
+            match transport.invoke(token, input) {
+                Ok(Ok(balance)) => Some(balance),
+                _ => None,
+            }
+        }
+
+        /// A transfer only went through if the call, the dispatch and the token all succeeded
+        fn transferred(result: CallResult<Result<(), PSP22Error>>) -> Result<(), Error> {
+            match result {
+                Ok(Ok(Ok(()))) => Ok(()),
+                _ => Err(Error::TokenTransferFailed),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    #[allow(clippy::bool_assert_comparison)]
+    mod tests {
+        use super::*;
+        use ink::env::call::ExecutionInput;
+        use mock_psp22::MockPsp22;
+        use std::{cell::RefCell, collections::BTreeMap};
+
+        thread_local! {
+            /// The mock tokens deployed in the current test, by address
+            static TOKENS: RefCell<BTreeMap<AccountId, MockPsp22>> = const { RefCell::new(BTreeMap::new()) };
+        }
+
+        /// Reach the mock tokens deployed in the current test the way a cross-contract call
+        /// would: the input and output go through SCALE, the message is picked by its PSP22
+        /// selector, and the token sees the betting contract as the caller.
+        pub(super) fn invoke_mock_token<Args: scale::Encode, R: scale::Decode>(
+            token: AccountId,
+            input: ExecutionInput<Args>,
+        ) -> psp22::CallResult<R> {
+            use scale::{Decode, Encode};
+
+            if !TOKENS.with(|tokens| tokens.borrow().contains_key(&token)) {
+                return Err(ink::env::Error::CalleeTrapped);
+            }
+            let contract = ink::env::test::callee::<Environment>();
+            let input = input.encode();
+            let (selector, args) = input.split_at(4);
+            let args = &mut &args[..];
+            let output = match selector {
+                [0xdb, 0x20, 0xf9, 0xf5] => {
+                    let (to, value, data) = Decode::decode(args).unwrap();
+                    with_token(token, contract, |t| t.transfer(to, value, data)).encode()
+                }
+                [0x54, 0xb3, 0xc7, 0x6e] => {
+                    let (from, to, value, data) = Decode::decode(args).unwrap();
+                    with_token(token, contract, |t| t.transfer_from(from, to, value, data)).encode()
+                }
+                [0x65, 0x68, 0x38, 0x2f] => {
+                    let owner = Decode::decode(args).unwrap();
+                    with_token(token, contract, |t| t.balance_of(owner)).encode()
+                }
+                _ => return Ok(Err(ink::LangError::CouldNotReadInput)),
+            };
+            R::decode(&mut &output[..])
+                .map(Ok)
+                .map_err(ink::env::Error::Decode)
+        }
+
+        /// A contract that reaches the mock tokens instead of making cross-contract calls
+        fn token_betting(final_decision_maker: AccountId, bet_creation_fee: Balance) -> Betting {
+            Betting::with_token_transport(
+                final_decision_maker,
+                bet_creation_fee,
+                psp22::Transport::Mock,
+            )
+        }
+
+        /// Deploy a mock PSP22 token at `token`, minting `supply` to `owner`
+        fn deploy_token(token: AccountId, owner: AccountId, supply: Balance) {
+            let (caller, contract) = (
+                ink::env::caller::<Environment>(),
+                ink::env::test::callee::<Environment>(),
+            );
+            ink::env::test::set_caller::<Environment>(owner);
+            ink::env::test::set_callee::<Environment>(token);
+            let mock = MockPsp22::new(supply);
+            TOKENS.with(|tokens| tokens.borrow_mut().insert(token, mock));
+            ink::env::test::set_caller::<Environment>(caller);
+            ink::env::test::set_callee::<Environment>(contract);
+        }
+
+        /// Call a mock token as `caller`, the way a cross-contract call would.
+        ///   The token's storage can only be reached while it is the callee.
+        fn with_token<R>(
+            token: AccountId,
+            caller: AccountId,
+            call: impl FnOnce(&mut MockPsp22) -> R,
+        ) -> R {
+            let (previous_caller, contract) = (
+                ink::env::caller::<Environment>(),
+                ink::env::test::callee::<Environment>(),
+            );
+            ink::env::test::set_caller::<Environment>(caller);
+            ink::env::test::set_callee::<Environment>(token);
+            let result = TOKENS.with(|tokens| {
+                call(
+                    tokens
+                        .borrow_mut()
+                        .get_mut(&token)
+                        .expect("no mock token deployed at this address"),
+                )
+            });
+            ink::env::test::set_caller::<Environment>(previous_caller);
+            ink::env::test::set_callee::<Environment>(contract);
+            result
+        }
+
+        fn default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+            ink::env::test::default_accounts::<Environment>()
+        }
+
+        /// Calls made after this carry no value unless made through `pay_with_call!`.
+        fn set_next_caller(caller: AccountId) {
+            ink::env::test::set_caller::<Environment>(caller);
+            ink::env::test::set_value_transferred::<Environment>(0);
+        }
+
+        fn create_sample_bet(
+            betting: &mut Betting,
+            bob: Option<AccountId>,
+            amount_to_wager: Balance,
+            fee: Balance,
+        ) -> u32 {
+            let event_concludes_by = String::from("2023-12-21T00:00:00Z");
+            let criteria_for_winning: String =
+                "Red wins game against blue on December 21st, 2023.".into();
+
+            let bet_number = ink::env::pay_with_call!(
+                betting.create_bet(
+                    amount_to_wager,
+                    amount_to_wager,
+                    bob,
+                    criteria_for_winning.clone(),
+                    event_concludes_by.clone(),
+                    false,
+                    None,
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                amount_to_wager + 2 * fee
+            )
+            .unwrap()
+            .unwrap();
+            assert_solvent(betting);
+            bet_number
+        }
+
+        /// The contract holds enough to cover everything it owes.
+        fn assert_solvent(betting: &Betting) {
+            let report = betting.solvency_report();
+            assert!(report.is_solvent, "{:?}", report);
+        }
+
+        /// Alice creates a commit-reveal bet that Bob accepts.
+        fn create_commit_reveal_bet(
+            betting: &mut Betting,
+            amount_to_wager: Balance,
+            fee: Balance,
+        ) -> u32 {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let bet_number = ink::env::pay_with_call!(
+                betting.create_bet(
+                    amount_to_wager,
+                    amount_to_wager,
+                    Some(bob),
+                    "Red wins game against blue on December 21st, 2023.".into(),
+                    "2023-12-21T00:00:00Z".into(),
+                    true,
+                    None,
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                amount_to_wager + fee
+            )
+            .unwrap()
+            .unwrap();
+
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            bet_number
+        }
+
+        /// Give the contract its own account, so its balance is not mixed up with Alice's.
+        fn use_separate_contract_account() -> AccountId {
+            let contract = AccountId::from([0x07; 32]);
+            ink::env::test::set_callee::<Environment>(contract);
+            if ink::env::test::get_account_balance::<Environment>(contract).is_err() {
+                ink::env::test::set_account_balance::<Environment>(contract, 0);
+            }
+            contract
+        }
+
+        /// Alice bets against Bob and both claim to have won.
+        fn create_disputed_bet(
+            betting: &mut Betting,
+            amount_to_wager: Balance,
+            fee: Balance,
+        ) -> u32 {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(betting, Some(bob), amount_to_wager, fee);
+
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            set_next_caller(bob);
+            betting.submit_outcome(bet_number, 2).unwrap();
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::BettorsDisagree)
+            );
+            assert_solvent(betting);
+
+            bet_number
+        }
+
+        /// Charlie reviews the disputed bet and rules that Bob won.
+        fn create_reviewed_bet(
+            betting: &mut Betting,
+            amount_to_wager: Balance,
+            fee: Balance,
+        ) -> u32 {
+            let alice = default_accounts().alice;
+            let charlie = default_accounts().charlie;
+
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            let bet_number = create_disputed_bet(betting, amount_to_wager, fee);
+
+            set_next_caller(alice);
+            assert_eq!(betting.assign_reviewer(bet_number), Ok(vec![charlie]));
+            set_next_caller(charlie);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Bettor2Wins)
+                .unwrap();
+            assert_solvent(betting);
+
+            bet_number
+        }
+
+        /// Charlie, Django and Eve register as reviewers and sit on a jury for a disputed bet.
+        fn create_jury_bet(betting: &mut Betting, amount_to_wager: Balance, fee: Balance) -> u32 {
+            let accounts = default_accounts();
+            let (alice, frank) = (accounts.alice, accounts.frank);
+
+            set_next_caller(frank);
+            betting.set_dispute_config(30, amount_to_wager, 3).unwrap();
+            for reviewer in [accounts.charlie, accounts.django, accounts.eve] {
+                ink::env::test::set_account_balance::<Environment>(reviewer, 1_000);
+                set_next_caller(reviewer);
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            }
+            let bet_number = create_disputed_bet(betting, amount_to_wager, fee);
+
+            set_next_caller(alice);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.assign_reviewer(bet_number), 29),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            let panel = ink::env::pay_with_call!(betting.assign_reviewer(bet_number), 30).unwrap();
+            assert_eq!(panel.len(), 3);
+            for reviewer in [accounts.charlie, accounts.django, accounts.eve] {
+                assert!(panel.contains(&reviewer));
+            }
+            assert_solvent(betting);
+
+            bet_number
+        }
+
+        #[cfg(any(feature = "runtime-benchmarks", feature = "std"))]
+        #[ink::test]
+        fn test_helper_function() {
+            let alice = default_accounts().alice;
+            set_next_caller(alice);
+            let betting = Betting::new(alice, 1);
+
+            let dt1 = String::from("2022-01-01T12:34:56Z");
+            let dt2 = String::from("2023-12-22T00:00:00Z");
+
+            // timestamp requires the `Z` at the end to specify UTC timezone
+            assert_eq!(
+                betting.has_past_datetime("2023-12-22T00:00:00".into()),
+                Err(Error::NotDatetimeString)
+            );
+            assert_eq!(
+                betting.convert_datetime_to_ms(dt1.clone()),
+                Ok(1_641_040_496_000)
+            );
+
+            assert_eq!(
+                betting.convert_datetime_to_ms(dt2.clone()),
+                Ok(1_703_203_200_000)
+            );
+        }
+
+        #[ink::test]
+        fn register_alice() {
+            let alice = default_accounts().alice;
+            set_next_caller(alice);
+            let mut betting = Betting::new(alice, 1);
+
+            // not registered yet
+            assert_eq!(
+                betting.is_registered_as_reviewer(),
+                Ok(false),
+                "Alice was already registered"
+            );
+
+            assert_eq!(
+                betting.number_of_reviewers, 0,
+                "Smart contract was initialized with at least 1 reviewer."
+            );
+
+            // but is final decision maker
+            assert_eq!(
+                betting.is_final_decision_maker(),
+                Ok(true),
+                "Alice is not the final decision maker"
+            );
+
+            let register = ink::env::pay_with_call!(betting.register_as_reviewer(), 1);
+            assert!(register.is_ok(), "Unable to register Alice.");
+
+            // registered
+            assert_eq!(
+                betting.is_registered_as_reviewer(),
+                Ok(true),
+                "Alice is not registered"
+            );
+
+            assert_eq!(betting.number_of_reviewers, 1, "Wrong number of reviewers.");
+        }
+
+        #[ink::test]
+        fn create_bet() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+            let event_concludes_by = String::from("2023-12-21T00:00:00Z");
+
+            set_next_caller(alice);
+
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+            assert!(betting.balance().unwrap() == 1_000_000); // alice has 1 million to begin with
+
+            let criteria_for_winning: String =
+                "Red wins game against blue on December 21st, 2023.".into();
+
+            let amount_to_wager = 100_000;
+            let amount_sent = amount_to_wager + 2 * fee;
+            let bet_number = ink::env::pay_with_call!(
+                betting.create_bet(
+                    amount_to_wager,
+                    amount_to_wager,
+                    Some(bob),
+                    criteria_for_winning.clone(),
+                    event_concludes_by.clone(),
+                    false,
+                    None,
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                amount_sent
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(bet_number, 0);
+
+            // check using getter methods
+            assert_eq!(
+                betting.get_bettor_account_id(bet_number, 1),
+                Ok(Some(alice))
+            );
+            assert_eq!(betting.get_bettor_account_id(bet_number, 2), Ok(Some(bob)));
+            assert_eq!(
+                betting.get_criteria_for_winning(bet_number),
+                Ok(criteria_for_winning)
+            );
+            assert_eq!(
+                betting.get_event_decided_by(bet_number),
+                Ok(event_concludes_by)
+            );
+            assert_eq!(
+                betting.get_event_decided_by_as_ms(bet_number),
+                Ok(1703116800000)
+            );
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Created));
+            let (stake_bettor_1, stake_bettor_2) = betting.get_stakes(bet_number).unwrap();
+            assert_eq!(
+                stake_bettor_1, amount_to_wager,
+                "Actual stake: {stake_bettor_1}"
+            );
+            assert_eq!(stake_bettor_2, amount_to_wager);
+        }
+
+        #[ink::test]
+        fn create_and_accept_bet_when_bettor2_is_assgined() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+            let charlie = default_accounts().charlie;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // Charlie should not be able to accept or reject the bet from Alice
+            set_next_caller(charlie);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 0),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Err(Error::NotBettor2)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.reject_bet(bet_number), 0),
+                Err(Error::NotBettor2)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.reject_bet(bet_number), amount_to_wager),
+                Err(Error::NotBettor2)
+            );
+
+            // Bob should be able to accept if he sent sufficient coins
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 0),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+        }
+
+        #[ink::test]
+        fn create_and_reject_bet_when_bettor2_is_assgined() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+            let charlie = default_accounts().charlie;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // Charlie should not be able to accept or reject the bet from Alice
+            set_next_caller(charlie);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 0),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Err(Error::NotBettor2)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.reject_bet(bet_number), 0),
+                Err(Error::NotBettor2)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.reject_bet(bet_number), amount_to_wager),
+                Err(Error::NotBettor2)
+            );
+
+            // Bob should be able to reject even if he sent zero coin
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 0),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.reject_bet(bet_number), 0),
+                Ok(true)
+            );
+        }
+
+        #[ink::test]
+        fn bettors_agree_on_outcome_alice_wins() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // alice creates bet
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // bob accepts bet
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+
+            // Event ends: Alice wins!
+            set_next_caller(alice);
+            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::Bettor1Voted)
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
+
+            set_next_caller(alice);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
+            // Cannot withdraw winnings a 2nd time
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::AlreadyWithdrewWinnings)
+            );
+        }
+
+        #[ink::test]
+        fn bettors_agree_on_outcome_bob_wins() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // alice creates bet
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // bob accepts bet
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+
+            // Event ends: Bob wins!
+            set_next_caller(alice);
+            assert_eq!(betting.submit_outcome(bet_number, 2).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::Bettor1Voted)
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.submit_outcome(bet_number, 2).is_ok(), true);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor2Wins));
+
+            // bob withdraws winnings
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
+            // Cannot withdraw winnings a 2nd time
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::AlreadyWithdrewWinnings)
+            );
+        }
+
+        #[ink::test]
+        fn bettors_agree_on_outcome_draw() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // alice creates bet
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // bob accepts bet
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+
+            // Event ends: Alice and Bob draws!
+            set_next_caller(alice);
+            assert_eq!(betting.submit_outcome(bet_number, 0).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::Bettor1Voted)
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.submit_outcome(bet_number, 0).is_ok(), true);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::BettorsDrew));
+
+            set_next_caller(alice);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
+            // Cannot withdraw winnings a 2nd time
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::AlreadyWithdrewWinnings)
+            );
+        }
+
+        #[ink::test]
+        fn bettors_agree_on_outcome_undecideable() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // alice creates bet
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // bob accepts bet
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+
+            // Event ends: bettors agree that conditions for winning turned out to be unclear!
+            set_next_caller(alice);
+            assert_eq!(betting.submit_outcome(bet_number, 3).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::Bettor1Voted)
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.submit_outcome(bet_number, 3).is_ok(), true);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::BettorsDrew));
+        }
+
+        #[ink::test]
+        fn bettors_disagree_on_outcome_voted_for_self() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // alice creates bet
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // bob accepts bet
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+
+            // Event ends: bettors disagree!
+            set_next_caller(alice);
+            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::Bettor1Voted)
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.submit_outcome(bet_number, 2).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::BettorsDisagree)
+            );
+        }
+
+        #[ink::test]
+        fn bettors_disagree_on_outcome_alice_voted_for_self_bob_drew() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // alice creates bet
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+
+            assert_eq!(
+                betting.get_stakes(bet_number),
+                Ok((amount_to_wager, amount_to_wager))
+            );
+
+            // bob accepts bet
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Ok(true)
+            );
+
+            // Event ends: bettors disagree!
+            set_next_caller(alice);
+            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::Bettor1Voted)
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.submit_outcome(bet_number, 0).is_ok(), true);
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::BettorsDisagree)
+            );
+        }
+
+        #[ink::test]
+        fn reviewer_settles_disputed_bet() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+            let charlie = default_accounts().charlie;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+
+            let bet_number = create_disputed_bet(&mut betting, amount_to_wager, fee);
+
+            // Charlie cannot rule before being assigned
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.submit_reviewer_outcome(bet_number, BetOutcome::Bettor2Wins),
+                Err(Error::NotAssignedReviewer)
+            );
+
+            // Bob assigns Charlie to review the bet
+            set_next_caller(bob);
+            assert_eq!(betting.assign_reviewer(bet_number), Ok(vec![charlie]));
+            assert_eq!(betting.get_reviewers(bet_number), Ok(vec![charlie]));
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::ReviewerAssigned)
+            );
+
+            // Bettors cannot rule on their own bet
+            assert_eq!(
+                betting.submit_reviewer_outcome(bet_number, BetOutcome::Bettor2Wins),
+                Err(Error::NotAssignedReviewer)
+            );
+
+            // Charlie rules in Bob's favor
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.submit_reviewer_outcome(bet_number, BetOutcome::Bettor2Wins),
+                Ok(())
+            );
+            assert_eq!(
+                betting.get_outcome_claimed_by_reviewer(bet_number),
+                Ok(Some(BetOutcome::Bettor2Wins))
+            );
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor2Wins));
+
+            // Charlie cannot rule a 2nd time
+            assert_eq!(
+                betting.submit_reviewer_outcome(bet_number, BetOutcome::Bettor1Wins),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+
+            // Bob has to wait until Alice can no longer appeal
+            set_next_caller(bob);
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::AppealWindowOpen)
+            );
+            ink::env::test::set_block_timestamp::<Environment>(DEFAULT_APPEAL_PERIOD + 1);
+
+            // Bob withdraws winnings
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
+        }
+
+        #[ink::test]
+        fn assign_reviewer_checks() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, django) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+            );
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+
+            // bets the bettors agree on cannot be reviewed
+            let agreed_bet = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            assert_eq!(
+                betting.assign_reviewer(agreed_bet),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+
+            let bet_number = create_disputed_bet(&mut betting, amount_to_wager, fee);
+
+            // Bob is the only reviewer but is a bettor
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_eq!(
+                betting.assign_reviewer(bet_number),
+                Err(Error::NoEligibleReviewer)
+            );
+
+            // Only bettors can assign a reviewer
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            set_next_caller(django);
+            assert_eq!(
+                betting.assign_reviewer(bet_number),
+                Err(Error::CallerNotValidBettor)
+            );
+            assert_eq!(betting.assign_reviewer(99), Err(Error::BetDoesNotExist));
+
+            // Charlie is the only eligible reviewer
+            set_next_caller(alice);
+            assert_eq!(betting.assign_reviewer(bet_number), Ok(vec![charlie]));
+        }
+
+        #[ink::test]
+        fn reviewer_registry_is_indexed() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, django) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+            );
+
+            let mut betting = Betting::new(alice, 1);
+            ink::env::test::set_account_balance::<Environment>(django, 10);
+            for reviewer in [alice, bob, charlie, django] {
+                set_next_caller(reviewer);
+                assert_eq!(
+                    ink::env::pay_with_call!(betting.register_as_reviewer(), 1),
+                    Ok(())
+                );
+            }
+
+            // registering twice does not count twice
+            assert_eq!(
+                ink::env::pay_with_call!(betting.register_as_reviewer(), 1),
+                Err(Error::AlreadyRegisteredReviewer)
+            );
+            assert_eq!(betting.get_number_of_reviewers(), 4);
+            assert_eq!(betting.list_reviewers(1, 2), vec![bob, charlie]);
+            assert_eq!(betting.list_reviewers(3, 10), vec![django]);
+            assert_eq!(betting.list_reviewers(4, 10), vec![]);
+
+            // Django takes over Bob's index once Bob leaves
+            set_next_caller(bob);
+            assert_eq!(betting.unregister_as_reviewer(), Ok(()));
+            assert_eq!(
+                betting.unregister_as_reviewer(),
+                Err(Error::NotRegisteredReviewer)
+            );
+            assert_eq!(betting.is_registered_as_reviewer(), Ok(false));
+            assert_eq!(betting.get_number_of_reviewers(), 3);
+            assert_eq!(betting.get_reviewer_at(1), Some(django));
+            assert_eq!(betting.get_reviewer_at(3), None);
+            assert_eq!(betting.list_reviewers(0, 10), vec![alice, django, charlie]);
+
+            // the last reviewer can leave too
+            set_next_caller(charlie);
+            assert_eq!(betting.unregister_as_reviewer(), Ok(()));
+            assert_eq!(betting.list_reviewers(0, 10), vec![alice, django]);
+        }
+
+        #[ink::test]
+        fn final_decision_maker_overturns_appealed_ruling() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+
+            set_next_caller(frank);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(frank, fee);
+            use_separate_contract_account();
+            let bet_number = create_reviewed_bet(&mut betting, amount_to_wager, fee);
+
+            // Bob won the ruling so only Alice can appeal
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee),
+                Err(Error::NotLosingBettor)
+            );
+            set_next_caller(alice);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee - 1),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee),
+                Ok(())
+            );
+            assert_solvent(&betting);
+            assert_eq!(betting.get_appellant(bet_number), Ok(Some(alice)));
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::RulingAppealed)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee),
+                Err(Error::AlreadyAppealed)
+            );
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+
+            // only the final decision maker can rule on the appeal
+            assert_eq!(
+                betting.submit_final_outcome(bet_number, BetOutcome::Bettor1Wins),
+                Err(Error::NotFinalDecisionMaker)
+            );
+
+            // Frank overturns the ruling: Alice gets her bond back plus half of Charlie's bond
+            let alice_claimable = betting.claimable_of(alice);
+            set_next_caller(frank);
+            assert_eq!(
+                betting.submit_final_outcome(bet_number, BetOutcome::Bettor1Wins),
+                Ok(())
+            );
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(alice), alice_claimable + fee + fee / 2);
+            assert_eq!(betting.get_reviewer_bond(charlie), fee - fee / 2);
+            assert_eq!(betting.get_open_cases(charlie), 0);
+            assert_eq!(
+                betting.get_outcome_decided_by_final_decision_maker(bet_number),
+                Ok(Some(BetOutcome::Bettor1Wins))
+            );
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
+
+            // the final decision is binding
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee),
+                Err(Error::AlreadyAppealed)
+            );
+            set_next_caller(alice);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_solvent(&betting);
+        }
+
+        #[ink::test]
+        fn final_decision_maker_upholds_appealed_ruling() {
+            let accounts = default_accounts();
+            let (alice, frank) = (accounts.alice, accounts.frank);
+
+            set_next_caller(frank);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(frank, fee);
+            use_separate_contract_account();
+            let bet_number = create_reviewed_bet(&mut betting, amount_to_wager, fee);
+
+            set_next_caller(alice);
+            ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee).unwrap();
+            assert_solvent(&betting);
+
+            // Frank upholds the ruling and Alice forfeits her bond
+            let alice_balance = ink::env::test::get_account_balance::<Environment>(alice).unwrap();
+            set_next_caller(frank);
+            assert_eq!(
+                betting.submit_final_outcome(bet_number, BetOutcome::Bettor2Wins),
+                Ok(())
+            );
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(alice),
+                Ok(alice_balance)
+            );
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor2Wins));
+        }
+
+        #[ink::test]
+        fn appeal_window_closes() {
+            let accounts = default_accounts();
+            let (alice, frank) = (accounts.alice, accounts.frank);
+
+            set_next_caller(frank);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(frank, fee);
+
+            // only the final decision maker can configure appeals
+            set_next_caller(alice);
+            assert_eq!(
+                betting.set_appeal_config(1_000, 5),
+                Err(Error::NotFinalDecisionMaker)
+            );
+            set_next_caller(frank);
+            assert_eq!(betting.set_appeal_config(1_000, 5), Ok(()));
+            assert_eq!(betting.get_appeal_config(), (1_000, 5));
+
+            let bet_number = create_reviewed_bet(&mut betting, amount_to_wager, fee);
+
+            ink::env::test::set_block_timestamp::<Environment>(1_001);
+            set_next_caller(alice);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), 5),
+                Err(Error::AppealWindowClosed)
+            );
+        }
+
+        #[ink::test]
+        fn reviewer_bond_is_locked_until_unbonded() {
+            let accounts = default_accounts();
+            let (alice, charlie) = (accounts.alice, accounts.charlie);
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+            use_separate_contract_account();
+
+            // only the final decision maker can configure bonds
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.set_reviewer_bond_config(fee, 5_000, 1_000),
+                Err(Error::NotFinalDecisionMaker)
+            );
+            set_next_caller(alice);
+            assert_eq!(
+                betting.set_reviewer_bond_config(fee, 10_001, 1_000),
+                Err(Error::InvalidBasisPoints)
+            );
+            // bonds cannot unlock while the rulings can still be appealed
+            assert_eq!(
+                betting.set_reviewer_bond_config(fee, 5_000, 1_000),
+                Err(Error::UnbondingPeriodTooShort)
+            );
+            betting.set_appeal_config(999, fee).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.set_reviewer_bond_config(fee, 5_000, 1_000), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.set_appeal_config(1_000, fee),
+                Err(Error::UnbondingPeriodTooShort)
+            );
+            assert_eq!(betting.get_reviewer_bond_config(), (fee, 5_000, 1_000));
+
+            // Charlie has to lock the full bond
+            set_next_caller(charlie);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee - 1),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee),
+                Ok(())
+            );
+            assert_solvent(&betting);
+            assert_eq!(betting.get_reviewer_bond(charlie), fee);
+            assert_eq!(betting.withdraw_reviewer_bond(), Err(Error::NotUnbonding));
+
+            // Charlie cannot leave while he has a case to rule on
+            let bet_number = create_disputed_bet(&mut betting, amount_to_wager, fee);
+            betting.assign_reviewer(bet_number).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_open_cases(charlie), 1);
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.unregister_as_reviewer(),
+                Err(Error::ReviewerHasOpenCases)
+            );
+
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Draw)
+                .unwrap();
+            assert_eq!(betting.get_open_cases(charlie), 0);
+            assert_eq!(betting.unregister_as_reviewer(), Ok(()));
+            assert_solvent(&betting);
+
+            // the bond stays locked for the unbonding period
+            assert_eq!(
+                betting.withdraw_reviewer_bond(),
+                Err(Error::BondStillLocked)
+            );
+            ink::env::test::set_block_timestamp::<Environment>(1_000);
+            let charlie_balance =
+                ink::env::test::get_account_balance::<Environment>(charlie).unwrap();
+            assert_eq!(betting.withdraw_reviewer_bond(), Ok(fee));
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(charlie),
+                Ok(charlie_balance + fee)
+            );
+            assert_eq!(betting.get_reviewer_bond(charlie), 0);
+            assert_eq!(betting.withdraw_reviewer_bond(), Err(Error::NotUnbonding));
+        }
+
+        #[ink::test]
+        fn reviewer_reputation_tracks_rulings() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+
+            set_next_caller(frank);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(frank, fee);
+            use_separate_contract_account();
+            assert_eq!(betting.get_reputation_score(charlie), 100);
+
+            // Charlie's first ruling gets overturned
+            let bet_number = create_reviewed_bet(&mut betting, amount_to_wager, fee);
+            set_next_caller(alice);
+            ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(frank);
+            betting
+                .submit_final_outcome(bet_number, BetOutcome::Bettor1Wins)
+                .unwrap();
+            assert_eq!(
+                betting.get_reviewer_stats(charlie),
+                ReviewerStats {
+                    cases_assigned: 1,
+                    rulings_upheld: 0,
+                    rulings_overturned: 1,
+                    missed_deadlines: 0,
+                }
+            );
+            assert_eq!(betting.get_reputation_score(charlie), 0);
+
+            // the slash left Charlie short of the reviewer bond, so he has to top it up
+            assert_eq!(betting.get_number_of_reviewers(), 0);
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee / 2).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(frank);
+
+            // Charlie is no longer trusted with disputes
+            assert_eq!(
+                betting.set_min_reputation_score(101),
+                Err(Error::InvalidReputationScore)
+            );
+            assert_eq!(betting.set_min_reputation_score(50), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(betting.get_min_reputation_score(), 50);
+            let bet_number = create_disputed_bet(&mut betting, amount_to_wager, fee);
+            assert_eq!(
+                betting.assign_reviewer(bet_number),
+                Err(Error::NoEligibleReviewer)
+            );
+
+            // Charlie's second ruling stands once nobody appeals it in time
+            set_next_caller(frank);
+            betting.set_min_reputation_score(0).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(bob);
+            assert_eq!(betting.assign_reviewer(bet_number), Ok(vec![charlie]));
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Bettor1Wins)
+                .unwrap();
+            ink::env::test::set_block_timestamp::<Environment>(DEFAULT_APPEAL_PERIOD + 1);
+            set_next_caller(alice);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.get_reviewer_stats(charlie),
+                ReviewerStats {
+                    cases_assigned: 2,
+                    rulings_upheld: 1,
+                    rulings_overturned: 1,
+                    missed_deadlines: 0,
+                }
+            );
+            assert_eq!(betting.get_reputation_score(charlie), 50);
+        }
+
+        #[ink::test]
+        fn jury_majority_decides_and_shares_dispute_fee() {
+            let accounts = default_accounts();
+            let (charlie, django, eve, frank) = (
+                accounts.charlie,
+                accounts.django,
+                accounts.eve,
+                accounts.frank,
+            );
+
+            set_next_caller(frank);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(frank, fee);
+            use_separate_contract_account();
+            assert_eq!(
+                betting.set_dispute_config(30, amount_to_wager, 2),
+                Err(Error::JurySizeMustBeOdd)
+            );
+
+            let bet_number = create_jury_bet(&mut betting, amount_to_wager, fee);
+            assert_eq!(
+                betting.get_reviewers(bet_number),
+                Ok(vec![eve, charlie, django])
+            );
+
+            set_next_caller(charlie);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Bettor2Wins)
+                .unwrap();
+            set_next_caller(django);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Bettor2Wins)
+                .unwrap();
+            assert_eq!(
+                betting.submit_reviewer_outcome(bet_number, BetOutcome::Bettor1Wins),
+                Err(Error::AlreadyVoted)
+            );
+
+            // the ruling waits for the whole panel
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::ReviewerAssigned)
+            );
+
+            let balances = [charlie, django, eve].map(|x| betting.claimable_of(x));
+            set_next_caller(eve);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Bettor1Wins)
+                .unwrap();
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor2Wins));
+            assert_eq!(
+                betting.get_outcome_claimed_by_reviewer(bet_number),
+                Ok(Some(BetOutcome::Bettor2Wins))
+            );
+
+            // Charlie and Django share the dispute fee and the reward for resolving the dispute,
+            // 30 + 5 split two ways, with the remainder going to Charlie who sits first on the
+            // panel of the two
+            assert_eq!(
+                [charlie, django, eve].map(|x| betting.claimable_of(x)),
+                [balances[0] + 18, balances[1] + 17, balances[2]]
+            );
+            assert_eq!(betting.get_reviewer_reward_pool(), 0);
+        }
+
+        #[ink::test]
+        fn jury_without_majority_refunds_bettors() {
+            let accounts = default_accounts();
+            let (alice, charlie, django, eve, frank) = (
+                accounts.alice,
+                accounts.charlie,
+                accounts.django,
+                accounts.eve,
+                accounts.frank,
+            );
+
+            set_next_caller(frank);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(frank, fee);
+            use_separate_contract_account();
+            let bet_number = create_jury_bet(&mut betting, amount_to_wager, fee);
+
+            let alice_claimable = betting.claimable_of(alice);
+            for (reviewer, outcome) in [
+                (charlie, BetOutcome::Bettor1Wins),
+                (django, BetOutcome::Bettor2Wins),
+                (eve, BetOutcome::Draw),
+            ] {
+                set_next_caller(reviewer);
+                betting
+                    .submit_reviewer_outcome(bet_number, outcome)
+                    .unwrap();
+            }
+
+            // without a majority the bettors are refunded, and so is the dispute fee
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::BettorsDrew));
+            assert_eq!(
+                betting.get_outcome_claimed_by_reviewer(bet_number),
+                Ok(Some(BetOutcome::Undecideable))
+            );
+            assert_eq!(betting.claimable_of(alice), alice_claimable + 30);
+        }
+
+        #[ink::test]
+        fn commit_reveal_bettors_agree() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+            let bet_number = create_commit_reveal_bet(&mut betting, amount_to_wager, fee);
+            assert_eq!(
+                betting.get_commit_reveal_deadlines(bet_number),
+                Ok(Some((1_703_203_200_000, 1_703_289_600_000)))
+            );
+
+            let alice_salt = [1; 32];
+            let bob_salt = [2; 32];
+
+            // outcomes cannot be submitted in the open
+            set_next_caller(alice);
+            assert_eq!(
+                betting.submit_outcome(bet_number, 1),
+                Err(Error::CommitRevealBet)
+            );
+            let commitment = betting.get_outcome_commitment(bet_number, alice, 1, alice_salt);
+            assert_eq!(betting.commit_outcome(bet_number, commitment), Ok(()));
+            assert_eq!(
+                betting.commit_outcome(bet_number, commitment),
+                Err(Error::AlreadyCommitted)
+            );
+
+            // Alice cannot reveal before Bob commits
+            assert_eq!(
+                betting.reveal_outcome(bet_number, 1, alice_salt),
+                Err(Error::RevealPhaseNotOpen)
+            );
+
+            set_next_caller(bob);
+            let commitment = betting.get_outcome_commitment(bet_number, bob, 1, bob_salt);
+            assert_eq!(betting.commit_outcome(bet_number, commitment), Ok(()));
+            assert_eq!(
+                betting.reveal_outcome(bet_number, 2, bob_salt),
+                Err(Error::CommitmentMismatch)
+            );
+            assert_eq!(betting.reveal_outcome(bet_number, 1, bob_salt), Ok(()));
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::BetAcceptedByBettor2)
+            );
+
+            set_next_caller(alice);
+            assert_eq!(betting.reveal_outcome(bet_number, 1, alice_salt), Ok(()));
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
+        }
+
+        #[ink::test]
+        fn commit_reveal_unrevealed_claim_does_not_count() {
+            let alice = default_accounts().alice;
+            let bob = default_accounts().bob;
+
+            set_next_caller(alice);
+            let amount_to_wager = 100;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(alice, fee);
+            let bet_number = create_commit_reveal_bet(&mut betting, amount_to_wager, fee);
+            let (_, reveal_deadline) = betting
+                .get_commit_reveal_deadlines(bet_number)
+                .unwrap()
+                .unwrap();
+
+            set_next_caller(alice);
+            let commitment = betting.get_outcome_commitment(bet_number, alice, 1, [1; 32]);
+            betting.commit_outcome(bet_number, commitment).unwrap();
+            set_next_caller(bob);
+            let commitment = betting.get_outcome_commitment(bet_number, bob, 2, [2; 32]);
+            betting.commit_outcome(bet_number, commitment).unwrap();
+
+            set_next_caller(alice);
+            betting.reveal_outcome(bet_number, 1, [1; 32]).unwrap();
+            assert_eq!(
+                betting.close_reveal_phase(bet_number),
+                Err(Error::RevealPhaseNotOver)
+            );
+
+            // Bob never reveals, so Alice's claim stands
+            ink::env::test::set_block_timestamp::<Environment>(reveal_deadline + 1);
+            set_next_caller(bob);
+            assert_eq!(
+                betting.reveal_outcome(bet_number, 2, [2; 32]),
+                Err(Error::RevealPhaseClosed)
+            );
+            assert_eq!(betting.close_reveal_phase(bet_number), Ok(()));
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
+        }
+
+        #[ink::test]
+        fn bet_creation_fees_are_distributed() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+            let fee = 100;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(alice);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        10,
+                        10,
+                        Some(bob),
+                        String::from("Red wins"),
+                        String::from("2023-12-21T00:00:00Z"),
+                        false,
+                        Some(alice),
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    10 + fee
+                ),
+                Err(Error::SelfReferral)
+            );
+
+            // Charlie refers Alice's bet; the second bet has no referrer
+            ink::env::pay_with_call!(
+                betting.create_bet(
+                    10,
+                    10,
+                    Some(bob),
+                    String::from("Red wins"),
+                    String::from("2023-12-21T00:00:00Z"),
+                    false,
+                    Some(charlie),
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                10 + fee
+            )
+            .unwrap();
+            assert_solvent(&betting);
+            create_sample_bet(&mut betting, Some(bob), 10, fee);
+            assert_eq!(betting.get_fee_distribution(), (5_000, 1_000, 50));
+            assert_eq!(betting.get_reviewer_reward_pool(), 100);
+            assert_eq!(betting.get_referral_rewards(charlie), 10);
+            assert_eq!(betting.get_treasury_balance(), 90);
+
+            set_next_caller(charlie);
+            let charlie_balance =
+                ink::env::test::get_account_balance::<Environment>(charlie).unwrap();
+            assert_eq!(betting.withdraw_referral_rewards(), Ok(10));
+            assert_solvent(&betting);
+            assert_eq!(betting.withdraw_referral_rewards(), Ok(0));
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(charlie).unwrap(),
+                charlie_balance + 10
+            );
+
+            // only the final decision maker changes the split, which cannot exceed the fee
+            assert_eq!(
+                betting.set_fee_distribution(0, 0, 0),
+                Err(Error::NotFinalDecisionMaker)
+            );
+            set_next_caller(frank);
+            assert_eq!(
+                betting.set_fee_distribution(9_000, 1_001, 0),
+                Err(Error::InvalidBasisPoints)
+            );
+            assert_eq!(betting.set_fee_distribution(9_000, 1_000, 0), Ok(()));
+            assert_solvent(&betting);
+        }
+
+        #[ink::test]
+        fn conflicted_reviewers_are_not_assigned_and_recusals_are_reassigned() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, django, eve, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+                accounts.eve,
+                accounts.frank,
+            );
+            let fee = 10;
+
+            let mut betting = Betting::new(frank, fee);
+            for reviewer in [charlie, django, eve] {
+                ink::env::test::set_account_balance::<Environment>(reviewer, 1_000);
+                set_next_caller(reviewer);
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            }
+
+            // Charlie recently bet against Alice
+            set_next_caller(alice);
+            let earlier_bet = create_sample_bet(&mut betting, Some(charlie), 10, fee);
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.accept_bet(earlier_bet, Vec::new()), 10).unwrap();
+            assert_eq!(betting.get_last_bet_between(charlie, alice), Some(0));
+
+            // Django referred Alice to the disputed bet
+            set_next_caller(alice);
+            let bet_number = ink::env::pay_with_call!(
+                betting.create_bet(
+                    10,
+                    10,
+                    Some(bob),
+                    String::from("Red wins"),
+                    String::from("2023-12-21T00:00:00Z"),
+                    false,
+                    Some(django),
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                10 + fee
+            )
+            .unwrap()
+            .unwrap();
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 10).unwrap();
+            betting.submit_outcome(bet_number, 2).unwrap();
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+
+            // only Eve is free of conflicts, and nobody can take her seat
+            assert_eq!(betting.assign_reviewer(bet_number), Ok(vec![eve]));
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.recuse_from_case(bet_number),
+                Err(Error::NotAssignedReviewer)
+            );
+            set_next_caller(eve);
+            assert_eq!(
+                betting.recuse_from_case(bet_number),
+                Err(Error::NoEligibleReviewer)
+            );
+
+            // once the final decision maker relaxes the rules, Charlie or Django takes over
+            set_next_caller(frank);
+            assert_eq!(
+                betting.get_conflict_of_interest_config(),
+                (30 * 24 * 60 * 60 * 1000, true)
+            );
+            betting.set_conflict_of_interest_config(0, false).unwrap();
+            set_next_caller(eve);
+            let replacement = betting.recuse_from_case(bet_number).unwrap();
+            assert!(replacement == charlie || replacement == django);
+            assert_eq!(betting.get_reviewers(bet_number), Ok(vec![replacement]));
+            assert_eq!(betting.get_recused_reviewers(bet_number), Ok(vec![eve]));
+            assert_eq!(betting.get_open_cases(eve), 0);
+            assert_eq!(betting.get_open_cases(replacement), 1);
+
+            set_next_caller(replacement);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Bettor1Wins)
+                .unwrap();
+            assert_eq!(
+                betting.recuse_from_case(bet_number),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+        }
+
+        #[ink::test]
+        fn overdue_reviewers_are_replaced_until_the_dispute_escalates() {
+            let accounts = default_accounts();
+            let (alice, charlie, django, frank) = (
+                accounts.alice,
+                accounts.charlie,
+                accounts.django,
+                accounts.frank,
+            );
+            let fee = 10;
+
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_ruling_deadline_config(100, 1).unwrap();
+            for reviewer in [charlie, django] {
+                ink::env::test::set_account_balance::<Environment>(reviewer, 1_000);
+                set_next_caller(reviewer);
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            }
+            let bet_number = create_disputed_bet(&mut betting, 10, fee);
+
+            set_next_caller(alice);
+            let first = betting.assign_reviewer(bet_number).unwrap()[0];
+            assert_eq!(betting.get_ruling_deadline(bet_number), Ok(Some(100)));
+            assert_eq!(
+                betting.reassign_overdue_reviewers(bet_number),
+                Err(Error::RulingDeadlineNotPassed)
+            );
+
+            // anyone can replace the reviewer once the deadline passes
+            ink::env::test::set_block_timestamp::<Environment>(101);
+            set_next_caller(frank);
+            let second = betting.reassign_overdue_reviewers(bet_number).unwrap()[0];
+            assert_ne!(first, second);
+            assert_eq!(betting.get_ruling_deadline(bet_number), Ok(Some(201)));
+            assert_eq!(betting.get_overdue_reviewers(bet_number), Ok(vec![first]));
+            assert_eq!(betting.get_reviewer_stats(first).missed_deadlines, 1);
+            assert_eq!(betting.get_reputation_score(first), 0);
+            assert_eq!(betting.get_open_cases(first), 0);
+            assert_eq!(betting.get_open_cases(second), 1);
+
+            // the second reviewer misses the deadline too, so the dispute escalates
+            ink::env::test::set_block_timestamp::<Environment>(202);
+            assert_eq!(betting.reassign_overdue_reviewers(bet_number), Ok(vec![]));
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::DisputeEscalated)
+            );
+            assert_eq!(betting.get_open_cases(second), 0);
+            set_next_caller(second);
+            assert_eq!(
+                betting.submit_reviewer_outcome(bet_number, BetOutcome::Bettor1Wins),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+            set_next_caller(alice);
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+
+            set_next_caller(frank);
+            betting
+                .submit_final_outcome(bet_number, BetOutcome::Bettor1Wins)
+                .unwrap();
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
+            set_next_caller(alice);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+        }
+
+        #[ink::test]
+        fn bettors_submit_bounded_evidence_before_the_deadline() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+            let fee = 10;
+
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_evidence_config(100, 2).unwrap();
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), 10, fee);
+            assert_eq!(
+                betting.submit_evidence(bet_number, Hash::from([1; 32]), "ipfs://a".into()),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+            assert_eq!(betting.get_evidence_deadline(bet_number), Ok(None));
+
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 10).unwrap();
+            betting.submit_outcome(bet_number, 2).unwrap();
+            ink::env::test::set_block_timestamp::<Environment>(50);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_eq!(betting.get_evidence_deadline(bet_number), Ok(Some(150)));
+
+            // each bettor can submit up to two short entries
+            for entry in 1..=2 {
+                assert_eq!(
+                    betting.submit_evidence(bet_number, Hash::from([entry; 32]), "ipfs://a".into()),
+                    Ok(())
+                );
+            }
+            assert_eq!(
+                betting.submit_evidence(bet_number, Hash::from([3; 32]), "ipfs://a".into()),
+                Err(Error::EvidenceLimitReached)
+            );
+            set_next_caller(bob);
+            assert_eq!(
+                betting.submit_evidence(bet_number, Hash::from([4; 32]), "a".repeat(257)),
+                Err(Error::EvidenceUriTooLong)
+            );
+            assert_eq!(
+                betting.submit_evidence(
+                    bet_number,
+                    Hash::from([4; 32]),
+                    "Video of the game".into()
+                ),
+                Ok(())
+            );
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.submit_evidence(bet_number, Hash::from([5; 32]), "ipfs://c".into()),
+                Err(Error::CallerNotValidBettor)
+            );
+
+            ink::env::test::set_block_timestamp::<Environment>(151);
+            set_next_caller(bob);
+            assert_eq!(
+                betting.submit_evidence(bet_number, Hash::from([6; 32]), "ipfs://b".into()),
+                Err(Error::EvidenceDeadlinePassed)
+            );
+
+            let evidence = betting.get_evidence(bet_number).unwrap();
+            assert_eq!(evidence.len(), 3);
+            assert_eq!(
+                evidence[2],
+                Evidence {
+                    submitted_by: bob,
+                    content_hash: Hash::from([4; 32]),
+                    uri: "Video of the game".into(),
+                    submitted_at: 50,
+                }
+            );
+        }
+
+        #[ink::test]
+        fn rejected_bet_refunds_wager_and_keeps_fee() {
+            let accounts = default_accounts();
+            let (alice, bob, frank) = (accounts.alice, accounts.bob, accounts.frank);
+            let (amount_to_wager, fee) = (100, 10);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(alice);
+            let balance_before = ink::env::test::get_account_balance::<Environment>(alice).unwrap();
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            let balance_after_creating =
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap();
+            assert_eq!(
+                balance_after_creating,
+                balance_before - amount_to_wager - 2 * fee
+            );
+
+            set_next_caller(bob);
+            assert_eq!(betting.reject_bet(bet_number), Ok(true));
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.reject_bet(bet_number),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+
+            // Alice gets the wager and the extra fee create_sample_bet sent back
+            set_next_caller(alice);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(alice), amount_to_wager + fee);
+            assert_eq!(betting.claim(amount_to_wager + fee), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap(),
+                balance_after_creating + amount_to_wager + fee
+            );
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::AlreadyWithdrewWinnings)
+            );
+            assert_eq!(
+                betting.get_treasury_balance() + betting.get_reviewer_reward_pool(),
+                fee
+            );
+        }
+
+        #[ink::test]
+        fn rejected_bet_refunds_part_of_the_fee() {
+            let accounts = default_accounts();
+            let (alice, bob, frank) = (accounts.alice, accounts.bob, accounts.frank);
+            let (amount_to_wager, fee) = (100, 10);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            assert_eq!(
+                betting.set_rejection_refund_config(10_001),
+                Err(Error::InvalidBasisPoints)
+            );
+            betting.set_rejection_refund_config(6_000).unwrap();
+            assert_solvent(&betting);
+
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            let balance_after_creating =
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap();
+            set_next_caller(bob);
+            betting.reject_bet(bet_number).unwrap();
+            assert_solvent(&betting);
+            let bob_balance = ink::env::test::get_account_balance::<Environment>(bob).unwrap();
+
+            // anyone can trigger the refund, which always goes to Alice
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(bob), 0);
+            set_next_caller(alice);
+            assert_eq!(betting.claim(betting.claimable_of(alice)), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap(),
+                balance_after_creating + amount_to_wager + 6 + fee
+            );
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(bob).unwrap(),
+                bob_balance
+            );
+            // the treasury held 5 of the fee, so the reviewer pool covers the rest
+            assert_eq!(betting.get_treasury_balance(), 0);
+            assert_eq!(betting.get_reviewer_reward_pool(), 4);
+        }
+
+        #[ink::test]
+        fn bettor1_cancels_unaccepted_bet() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie) = (accounts.alice, accounts.bob, accounts.charlie);
+            let (amount_to_wager, fee) = (100, 10);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, fee);
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, None, amount_to_wager, fee);
+            let balance_after_creating =
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap();
+
+            set_next_caller(charlie);
+            assert_eq!(betting.cancel_bet(bet_number), Err(Error::NotBettor1));
+            set_next_caller(alice);
+            assert_eq!(betting.cancel_bet(bet_number), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Cancelled));
+            assert_eq!(betting.claimable_of(alice), amount_to_wager + fee);
+            assert_eq!(betting.claim(amount_to_wager), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap(),
+                balance_after_creating + amount_to_wager
+            );
+            assert_eq!(
+                betting.cancel_bet(bet_number),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::BetCancelled)
+            );
+
+            // nobody can take the offer any more
+            set_next_caller(charlie);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Err(Error::BetCancelled)
+            );
+
+            // an accepted bet can no longer be cancelled
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            assert_eq!(
+                betting.cancel_bet(bet_number),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+        }
+
+        #[ink::test]
+        fn overpayments_are_credited_to_the_sender() {
+            let accounts = default_accounts();
+            let (alice, bob, frank) = (accounts.alice, accounts.bob, accounts.frank);
+            let (amount_to_wager, fee) = (100, 10);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_dispute_config(5, Balance::MAX, 3).unwrap();
+            assert_solvent(&betting);
+
+            // create_sample_bet sends twice the creation fee
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            assert_eq!(betting.claimable_of(alice), fee);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(
+                betting.accept_bet(bet_number, Vec::new()),
+                amount_to_wager + 7
+            )
+            .unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(bob), 7);
+
+            betting.submit_outcome(bet_number, 2).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(accounts.charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            ink::env::pay_with_call!(betting.assign_reviewer(bet_number), 8).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(alice), fee + 3);
+
+            let balance_before = ink::env::test::get_account_balance::<Environment>(alice).unwrap();
+            assert_eq!(betting.claim(fee + 3), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(betting.claim(1), Err(Error::InsufficientClaimableBalance));
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(alice).unwrap(),
+                balance_before + fee + 3
+            );
+        }
+
+        #[ink::test]
+        fn draw_is_credited_to_both_bettors_once() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let (amount_to_wager, fee) = (100, 10);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, fee);
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            assert_solvent(&betting);
+            betting.submit_outcome(bet_number, 0).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 0).unwrap();
+            assert_solvent(&betting);
+
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::AlreadyWithdrewWinnings)
+            );
+            assert_eq!(betting.claimable_of(alice), amount_to_wager + fee);
+            assert_eq!(betting.claimable_of(bob), amount_to_wager);
+
+            // Bob claims his stake in two goes, but never more than he is owed
+            set_next_caller(bob);
+            let bob_balance = ink::env::test::get_account_balance::<Environment>(bob).unwrap();
+            assert_eq!(betting.claim(40), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(betting.claim(61), Err(Error::InsufficientClaimableBalance));
+            assert_eq!(betting.claim(60), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(bob), 0);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(bob).unwrap(),
+                bob_balance + amount_to_wager
+            );
+        }
+
+        #[ink::test]
+        fn only_decided_bets_settle_and_only_for_bettors_or_keepers() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+            let (amount_to_wager, fee) = (100, 10);
+
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            let assert_unsettled = |betting: &mut Betting, state: BetState| {
+                set_next_caller(alice);
+                assert_eq!(
+                    betting.withdraw_winnings(bet_number),
+                    Err(Error::InvalidStateForCallingFunction)
+                );
+                assert_eq!(betting.get_bet_state(bet_number), Ok(state));
+            };
+
+            assert_unsettled(&mut betting, BetState::Created);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            assert_unsettled(&mut betting, BetState::BetAcceptedByBettor2);
+            set_next_caller(bob);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_unsettled(&mut betting, BetState::Bettor2Voted);
+            betting.submit_outcome(bet_number, 2).unwrap();
+            assert_unsettled(&mut betting, BetState::BettorsDisagree);
+
+            // the bettors agree on their second bet, which Charlie settles once he is a keeper
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            betting.submit_outcome(bet_number, 2).unwrap();
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 2).unwrap();
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.withdraw_winnings(bet_number),
+                Err(Error::NotBettorOrKeeper)
+            );
+            assert_eq!(
+                betting.set_keeper(charlie, true),
+                Err(Error::NotFinalDecisionMaker)
+            );
+            set_next_caller(frank);
+            betting.set_keeper(charlie, true).unwrap();
+            assert!(betting.is_keeper(charlie));
+            set_next_caller(charlie);
+            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
+            assert_eq!(betting.claimable_of(bob), 2 * amount_to_wager);
+            assert_eq!(betting.claimable_of(charlie), 0);
+
+            set_next_caller(frank);
+            betting.set_keeper(charlie, false).unwrap();
+            assert!(!betting.is_keeper(charlie));
+        }
+
+        #[ink::test]
+        fn transition_table_only_concludes_terminal_outcomes() {
+            use BetState::*;
+            let states = [
+                Created,
+                BetAcceptedByBettor2,
+                BetRefusedByBettor2,
+                Cancelled,
+                Bettor1Voted,
+                Bettor2Voted,
+                Bettor1Wins,
+                Bettor2Wins,
+                BettorsDrew,
+                BettorsDisagree,
+                ReviewerAssigned,
+                RulingAppealed,
+                DisputeEscalated,
+                Concluded,
+            ];
+
+            for from in states {
+                let settles = matches!(
+                    from,
+                    Bettor1Wins | Bettor2Wins | BettorsDrew | BetRefusedByBettor2
+                );
+                assert_eq!(
+                    Betting::is_valid_transition(from, Concluded),
+                    settles,
+                    "{:?}",
+                    from
+                );
+                for to in states {
+                    if matches!(from, Cancelled | Concluded) || to == Created {
+                        assert!(
+                            !Betting::is_valid_transition(from, to),
+                            "{:?} -> {:?}",
+                            from,
+                            to
+                        );
+                    }
+                }
+            }
+            assert!(Betting::is_valid_transition(Created, Cancelled));
+            assert!(!Betting::is_valid_transition(
+                BetAcceptedByBettor2,
+                Cancelled
+            ));
+            assert!(Betting::is_valid_transition(
+                BettorsDisagree,
+                ReviewerAssigned
+            ));
+            assert!(!Betting::is_valid_transition(BettorsDisagree, Bettor1Wins));
+            assert!(Betting::is_valid_transition(
+                ReviewerAssigned,
+                DisputeEscalated
+            ));
+            assert!(Betting::is_valid_transition(RulingAppealed, BettorsDrew));
+        }
+
+        #[ink::test]
+        fn admin_withdraws_fees_without_touching_liabilities() {
+            let accounts = default_accounts();
+            let (alice, bob, eve, frank) =
+                (accounts.alice, accounts.bob, accounts.eve, accounts.frank);
+            let (amount_to_wager, fee) = (100, 10);
+
+            let contract = use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(alice);
+            create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            assert_eq!(betting.get_treasury_balance(), 5);
+            assert_eq!(betting.get_admin(), frank);
+
+            assert_eq!(betting.withdraw_fees(alice, 1), Err(Error::NotAdmin));
+            set_next_caller(frank);
+            assert_eq!(
+                betting.withdraw_fees(eve, 6),
+                Err(Error::InsufficientTreasuryBalance)
+            );
+
+            // the contract holds the wager, Alice's overpayment and the reviewer pool's share
+            // besides the treasury, so only 2 of the treasury can go if 3 went missing
+            ink::env::test::set_account_balance::<Environment>(
+                contract,
+                amount_to_wager + 2 * fee - 3,
+            );
+            assert_eq!(
+                betting.withdraw_fees(eve, 5),
+                Err(Error::LiabilitiesNotCovered)
+            );
+            assert_eq!(betting.withdraw_fees(eve, 2), Ok(()));
+            assert_solvent(&betting);
+            assert_eq!(
+                ink::env::test::get_account_balance::<Environment>(eve),
+                Ok(2)
+            );
+            assert_eq!(betting.get_treasury_balance(), 3);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+
+            // the admin role can be handed over
+            betting.set_admin(eve).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.withdraw_fees(eve, 1), Err(Error::NotAdmin));
+        }
+
+        #[ink::test]
+        fn solvency_report_tracks_liabilities_through_a_bet() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let (amount_to_wager, fee) = (100, 10);
+
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+            let mut betting = Betting::new(alice, fee);
+            let report = |liabilities, treasury, free_balance| SolvencyReport {
+                liabilities,
+                treasury,
+                free_balance,
+                is_solvent: true,
+            };
+            assert_eq!(betting.solvency_report(), report(0, 0, 0));
+
+            // the wager, the overpaid fee and the reviewer pool's share are owed
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            assert_eq!(betting.solvency_report(), report(115, 5, 0));
+
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            assert_eq!(betting.solvency_report(), report(215, 5, 0));
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_eq!(betting.solvency_report(), report(215, 5, 0));
+
+            // settling moves the escrow into Alice's claimable balance
+            betting.withdraw_winnings(bet_number).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(alice), 2 * amount_to_wager + fee);
+            assert_eq!(betting.solvency_report(), report(215, 5, 0));
+            betting.claim(2 * amount_to_wager + fee).unwrap();
+            assert_eq!(betting.solvency_report(), report(5, 5, 0));
+
+            // whatever is sent to the contract directly is free balance
+            ink::env::test::set_account_balance::<Environment>(contract, 17);
+            assert_eq!(betting.solvency_report(), report(5, 5, 7));
+            ink::env::test::set_account_balance::<Environment>(contract, 4);
+            assert!(!betting.solvency_report().is_solvent);
+        }
+
+        #[ink::test]
+        fn pseudo_random_covers_the_whole_u8_range() {
+            let mut betting = Betting::new(default_accounts().alice, 10);
+            for salt in 0..10 {
+                betting.salt = salt;
+                let byte = betting.pseudo_random_hash()[0];
+                assert_eq!(betting.get_pseudo_random(0), 0);
+                // 255 takes the hash byte as it is rather than reducing it modulo 0
+                assert_eq!(betting.get_pseudo_random(u8::MAX), byte);
+                assert_eq!(betting.get_pseudo_random(99), byte % 100);
+                assert!(betting.get_pseudo_random(99) <= 99);
+            }
+        }
+
+        #[ink::test]
+        fn reading_a_pseudo_random_number_does_not_move_the_selection_seed() {
+            let mut betting = Betting::new(default_accounts().alice, 10);
+            let number = betting.get_pseudo_random(u8::MAX);
+            assert_eq!(betting.get_pseudo_random(u8::MAX), number);
+            assert_eq!(betting.salt, 0);
+
+            // only selecting reviewers advances it
+            betting.pseudo_random_index(3);
+            assert_eq!(betting.salt, 1);
+        }
+
+        #[ink::test]
+        fn create_bet_rejects_wagers_that_overflow() {
+            let alice = default_accounts().alice;
+            let fee = 10;
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+            ink::env::test::set_account_balance::<Environment>(alice, Balance::MAX);
+            let mut betting = Betting::new(alice, fee);
+            let create = |betting: &mut Betting, stakes: (Balance, Balance), sent: Balance| {
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        stakes.0,
+                        stakes.1,
+                        None,
+                        "Red wins game against blue on December 21st, 2023.".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        false,
+                        None,
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    sent
+                )
+            };
+            assert_solvent(&betting);
+
+            // the fee and the wager do not add up to a balance
+            set_next_caller(alice);
+            assert_eq!(
+                create(&mut betting, (Balance::MAX - fee + 1, 1), 0),
+                Err(Error::Overflow)
+            );
+            // the stakes fit, but the winner's pot would not
+            let amount_to_wager = Balance::MAX / 2 + 1;
+            assert_eq!(
+                create(
+                    &mut betting,
+                    (amount_to_wager, amount_to_wager),
+                    amount_to_wager + fee
+                ),
+                Err(Error::Overflow)
+            );
+            assert_eq!(betting.get_bet_state(0), Err(Error::BetDoesNotExist));
+            assert_eq!(betting.solvency_report().liabilities, 0);
+        }
+
+        #[ink::test]
+        fn largest_pot_settles_without_overflow() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let amount_to_wager = Balance::MAX / 2;
+
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+            ink::env::test::set_account_balance::<Environment>(alice, amount_to_wager);
+            ink::env::test::set_account_balance::<Environment>(bob, amount_to_wager);
+            let mut betting = Betting::new(alice, 0);
+
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, 0);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            assert_solvent(&betting);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_solvent(&betting);
+            betting.withdraw_winnings(bet_number).unwrap();
+            assert_solvent(&betting);
+
+            assert_eq!(betting.claimable_of(alice), Balance::MAX - 1);
+            assert_solvent(&betting);
+        }
+
+        #[ink::test]
+        fn counters_deadlines_and_claimable_balances_report_overflow() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie) = (accounts.alice, accounts.bob, accounts.charlie);
+            let (amount_to_wager, fee) = (100, 10);
+            let mut betting = Betting::new(alice, fee);
+
+            // bet numbers
+            betting.latest_bet = u32::MAX;
+            set_next_caller(alice);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        amount_to_wager,
+                        amount_to_wager,
+                        Some(bob),
+                        "Red wins game against blue on December 21st, 2023.".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        false,
+                        None,
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    amount_to_wager + fee
+                ),
+                Err(Error::Overflow)
+            );
+            betting.latest_bet = 0;
+
+            // commit and reveal deadlines
+            betting.set_commit_reveal_config(Timestamp::MAX, 1).unwrap();
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        amount_to_wager,
+                        amount_to_wager,
+                        Some(bob),
+                        "Red wins game against blue on December 21st, 2023.".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        true,
+                        None,
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    amount_to_wager + fee
+                ),
+                Err(Error::Overflow)
+            );
+
+            // evidence deadlines
+            betting.set_evidence_config(Timestamp::MAX, 5).unwrap();
+            ink::env::test::set_block_timestamp::<Environment>(1);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), amount_to_wager)
+                .unwrap();
+            assert_eq!(betting.submit_outcome(bet_number, 2), Err(Error::Overflow));
+            assert_eq!(
+                betting.get_bet_state(bet_number),
+                Ok(BetState::BetAcceptedByBettor2)
+            );
+
+            // the reviewer count
+            betting.number_of_reviewers = u32::MAX;
+            set_next_caller(charlie);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee),
+                Err(Error::Overflow)
+            );
+            betting.number_of_reviewers = 0;
+
+            // claimable balances
+            betting
+                .credit(charlie, Balance::MAX - betting.total_claimable)
+                .unwrap();
+            assert_eq!(betting.credit(bob, 1), Err(Error::Overflow));
+            assert_eq!(betting.claimable_of(bob), 0);
+        }
+
+        #[ink::test]
+        fn quote_fee_follows_the_fee_model() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let mut betting = Betting::new(alice, 10);
+            let quote =
+                |bettor_1_fee, bettor_2_fee, rake_if_bettor_1_wins, rake_if_bettor_2_wins| {
+                    Ok(FeeQuote {
+                        bettor_1_fee,
+                        bettor_2_fee,
+                        rake_if_bettor_1_wins,
+                        rake_if_bettor_2_wins,
+                    })
+                };
+            assert_eq!(betting.quote_fee(1_000, 1_000), quote(10, 0, 0, 0));
+            assert_eq!(betting.quote_fee(Balance::MAX, 1), Err(Error::Overflow));
+
+            set_next_caller(bob);
+            assert_eq!(
+                betting.set_fee_model(FeeModel::Flat(1), FeePayer::Both),
+                Err(Error::NotFinalDecisionMaker)
+            );
+            set_next_caller(alice);
+            assert_eq!(
+                betting.set_fee_model(FeeModel::Percentage(10_001), FeePayer::Both),
+                Err(Error::InvalidBasisPoints)
+            );
+            assert_eq!(
+                betting.set_fee_model(FeeModel::Rake(10_001), FeePayer::Both),
+                Err(Error::InvalidBasisPoints)
+            );
+            assert_eq!(
+                betting.set_fee_model(FeeModel::Tiered(vec![(100, 5), (100, 7)]), FeePayer::Both),
+                Err(Error::InvalidFeeTiers)
+            );
+            assert_eq!(
+                betting.get_fee_model(),
+                (FeeModel::Flat(10), FeePayer::Bettor1)
+            );
+
+            betting
+                .set_fee_model(FeeModel::Percentage(250), FeePayer::Both)
+                .unwrap();
+            assert_eq!(betting.quote_fee(1_000, 1_000), quote(25, 25, 0, 0));
+            // each bettor pays a percentage of their own stake
+            assert_eq!(betting.quote_fee(1_000, 200), quote(25, 5, 0, 0));
+
+            let tiers = vec![(10, 1), (100, 5), (1_000, 20)];
+            betting
+                .set_fee_model(FeeModel::Tiered(tiers.clone()), FeePayer::Bettor2)
+                .unwrap();
+            assert_eq!(
+                betting.get_fee_model(),
+                (FeeModel::Tiered(tiers), FeePayer::Bettor2)
+            );
+            assert_eq!(betting.quote_fee(9, 9), quote(0, 0, 0, 0));
+            assert_eq!(betting.quote_fee(99, 99), quote(0, 1, 0, 0));
+            assert_eq!(betting.quote_fee(100, 100), quote(0, 5, 0, 0));
+            assert_eq!(betting.quote_fee(5_000, 5_000), quote(0, 20, 0, 0));
+            assert_eq!(betting.quote_fee(5_000, 99), quote(0, 1, 0, 0));
+
+            // a rake is never paid up front, whoever the payer is
+            betting
+                .set_fee_model(FeeModel::Rake(500), FeePayer::Both)
+                .unwrap();
+            assert_eq!(betting.quote_fee(1_000, 1_000), quote(0, 0, 50, 50));
+            // the winner is raked on what they won, which is the other side's stake
+            assert_eq!(betting.quote_fee(1_000, 200), quote(0, 0, 10, 50));
+        }
+
+        #[ink::test]
+        fn both_bettors_pay_the_fee_when_configured() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let (amount_to_wager, fee) = (100, 10);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, fee);
+            betting
+                .set_fee_model(FeeModel::Flat(fee), FeePayer::Both)
+                .unwrap();
+            let bet_number = ink::env::pay_with_call!(
+                betting.create_bet(
+                    amount_to_wager,
+                    amount_to_wager,
+                    Some(bob),
+                    "Red wins game against blue on December 21st, 2023.".into(),
+                    "2023-12-21T00:00:00Z".into(),
+                    false,
+                    None,
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                amount_to_wager + fee
+            )
+            .unwrap()
+            .unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_acceptance_fee(bet_number), Ok(fee));
+
+            // the fee only applies to new bets
+            betting
+                .set_fee_model(FeeModel::Flat(fee), FeePayer::Bettor1)
+                .unwrap();
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                ),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            ink::env::pay_with_call!(
+                betting.accept_bet(bet_number, Vec::new()),
+                amount_to_wager + fee
+            )
+            .unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(bob), 0);
+            assert_eq!(betting.get_reviewer_reward_pool(), fee);
+            assert_eq!(betting.get_treasury_balance(), fee);
+            assert_solvent(&betting);
+        }
+
+        #[ink::test]
+        fn rake_is_taken_from_the_winnings_at_settlement() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let amount_to_wager = 100;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, 0);
+            betting
+                .set_fee_model(FeeModel::Rake(1_000), FeePayer::Bettor1)
+                .unwrap();
+            let play = |betting: &mut Betting, winner: u8| {
+                set_next_caller(alice);
+                let bet_number = create_sample_bet(betting, Some(bob), amount_to_wager, 0);
+                set_next_caller(bob);
+                ink::env::pay_with_call!(
+                    betting.accept_bet(bet_number, Vec::new()),
+                    amount_to_wager
+                )
+                .unwrap();
+                betting.submit_outcome(bet_number, winner).unwrap();
+                set_next_caller(alice);
+                betting.submit_outcome(bet_number, winner).unwrap();
+                betting.withdraw_winnings(bet_number).unwrap();
+                assert_solvent(betting);
+            };
+
+            // Bob wins Alice's wager less the 10% rake
+            play(&mut betting, 2);
+            assert_eq!(betting.claimable_of(bob), 2 * amount_to_wager - 10);
+            assert_eq!(betting.get_reviewer_reward_pool(), 5);
+            assert_eq!(betting.get_treasury_balance(), 5);
+
+            // nobody wins anything in a draw, so nothing is raked
+            play(&mut betting, 0);
+            assert_eq!(betting.claimable_of(alice), amount_to_wager);
+            assert_eq!(betting.claimable_of(bob), 3 * amount_to_wager - 10);
+            assert_eq!(betting.get_treasury_balance(), 5);
+        }
+
+        #[ink::test]
+        fn uneven_stakes_pay_the_whole_pot_to_the_winner() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            // Alice backs the favourite at 3:1
+            let (stake_alice, stake_bob) = (300, 100);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, 0);
+            let play = |betting: &mut Betting, winner: u8| {
+                set_next_caller(alice);
+                let bet_number = ink::env::pay_with_call!(
+                    betting.create_bet(
+                        stake_alice,
+                        stake_bob,
+                        Some(bob),
+                        "Red wins game against blue on December 21st, 2023.".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        false,
+                        None,
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    stake_alice
+                )
+                .unwrap()
+                .unwrap();
+                assert_eq!(betting.get_stakes(bet_number), Ok((stake_alice, stake_bob)));
+
+                // Bob only has to match his own stake
+                set_next_caller(bob);
+                assert_eq!(
+                    ink::env::pay_with_call!(
+                        betting.accept_bet(bet_number, Vec::new()),
+                        stake_bob - 1
+                    ),
+                    Err(Error::InssufficientAmountOfTokensSent)
+                );
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), stake_bob)
+                    .unwrap();
+                assert_solvent(betting);
+                betting.submit_outcome(bet_number, winner).unwrap();
+                set_next_caller(alice);
+                betting.submit_outcome(bet_number, winner).unwrap();
+                betting.withdraw_winnings(bet_number).unwrap();
+                assert_solvent(betting);
+            };
+
+            // a draw gives each side their own stake back
+            play(&mut betting, 0);
+            assert_eq!(betting.claimable_of(alice), stake_alice);
+            assert_eq!(betting.claimable_of(bob), stake_bob);
+
+            // the underdog takes both stakes
+            play(&mut betting, 2);
+            assert_eq!(betting.claimable_of(alice), stake_alice);
+            assert_eq!(betting.claimable_of(bob), 2 * stake_bob + stake_alice);
+        }
+
+        /// Create a bet on `token` that Alice pays for with `native_value` of the native currency
+        fn create_token_bet(
+            betting: &mut Betting,
+            token: AccountId,
+            amount_to_wager: Balance,
+            referrer: Option<AccountId>,
+            native_value: Balance,
+        ) -> Result<Option<u32>, Error> {
+            set_next_caller(default_accounts().alice);
+            ink::env::pay_with_call!(
+                betting.create_bet(
+                    amount_to_wager,
+                    amount_to_wager,
+                    Some(default_accounts().bob),
+                    "Red wins game against blue on December 21st, 2023.".into(),
+                    "2023-12-21T00:00:00Z".into(),
+                    false,
+                    referrer,
+                    Some(token),
+                    Vec::new(),
+                    Vec::new()
+                ),
+                native_value
+            )
+        }
+
+        #[ink::test]
+        fn token_bet_settles_in_the_token() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+            let (amount_to_wager, fee) = (100, 10);
+            let token = AccountId::from([0x09; 32]);
+
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+            deploy_token(token, alice, 1_000);
+            with_token(token, alice, |t| t.transfer(bob, 500, Vec::new())).unwrap();
+            let balance_of = |account| with_token(token, alice, |t| t.balance_of(account));
+            let mut betting = token_betting(alice, fee);
+
+            // the wager and fee are pulled against an allowance
+            assert_eq!(
+                create_token_bet(&mut betting, token, amount_to_wager, Some(charlie), 0),
+                Err(Error::TokenTransferFailed)
+            );
+            with_token(token, alice, |t| t.approve(contract, amount_to_wager + fee)).unwrap();
+            let bet_number =
+                create_token_bet(&mut betting, token, amount_to_wager, Some(charlie), 0)
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(betting.get_token(bet_number), Ok(Some(token)));
+            assert_eq!(balance_of(contract), amount_to_wager + fee);
+
+            // the fee stays in the token, with the referrer's share theirs to claim
+            assert_eq!(betting.token_claimable_of(token, charlie), 1);
+            assert_eq!(betting.get_token_treasury_balance(token), fee - 1);
+            assert_eq!(betting.get_treasury_balance(), 0);
+            assert_eq!(betting.get_reviewer_reward_pool(), 0);
+
+            set_next_caller(bob);
+            assert_eq!(
+                betting.accept_bet(bet_number, Vec::new()),
+                Err(Error::TokenTransferFailed)
+            );
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Created));
+            with_token(token, bob, |t| t.approve(contract, amount_to_wager)).unwrap();
+            betting.accept_bet(bet_number, Vec::new()).unwrap();
+            assert_solvent(&betting);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 1).unwrap();
+            assert_solvent(&betting);
+            betting.withdraw_winnings(bet_number).unwrap();
+            assert_solvent(&betting);
+
+            assert_eq!(betting.claimable_of(alice), 0);
+            assert_eq!(
+                betting.token_claimable_of(token, alice),
+                2 * amount_to_wager
+            );
+            assert_eq!(
+                betting.claim_tokens(token, 2 * amount_to_wager + 1),
+                Err(Error::InsufficientClaimableBalance)
+            );
+            betting.claim_tokens(token, 2 * amount_to_wager).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(balance_of(alice), 1_000 - 500 - fee + amount_to_wager);
+
+            // the admin withdraws the fees kept in the token
+            set_next_caller(bob);
+            assert_eq!(
+                betting.withdraw_token_fees(token, bob, fee - 1),
+                Err(Error::NotAdmin)
+            );
+            set_next_caller(alice);
+            betting.withdraw_token_fees(token, frank, fee - 1).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            betting.claim_tokens(token, 1).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(balance_of(frank), fee - 1);
+            assert_eq!(balance_of(contract), 0);
+            assert_solvent(&betting);
+        }
+
+        #[ink::test]
+        fn token_bet_refunds_in_the_token() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let (amount_to_wager, fee) = (100, 10);
+            let token = AccountId::from([0x09; 32]);
+
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+            deploy_token(token, alice, 1_000);
+            with_token(token, alice, |t| t.approve(contract, 1_000)).unwrap();
+            let mut betting = token_betting(alice, fee);
+            betting.set_rejection_refund_config(10_000).unwrap();
+            assert_solvent(&betting);
+
+            // nothing is owed in the native currency, so whatever is sent is credited back
+            let cancelled = create_token_bet(&mut betting, token, amount_to_wager, None, 5)
+                .unwrap()
+                .unwrap();
+            assert_eq!(betting.claimable_of(alice), 5);
+            betting.cancel_bet(cancelled).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.token_claimable_of(token, alice), amount_to_wager);
+
+            // a rejected bet's wager and fee come back in the token too
+            let rejected = create_token_bet(&mut betting, token, amount_to_wager, None, 0)
+                .unwrap()
+                .unwrap();
+            set_next_caller(bob);
+            betting.reject_bet(rejected).unwrap();
+            assert_solvent(&betting);
+            betting.withdraw_winnings(rejected).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.token_claimable_of(token, alice),
+                2 * amount_to_wager + fee
+            );
+            assert_eq!(betting.get_token_treasury_balance(token), fee);
+            assert_solvent(&betting);
+        }
+
+        /// When the sample pool closes (in unix timestamp, milliseconds)
+        const POOL_CUTOFF: Timestamp = 1_703_116_800_000;
+
+        /// Alice creates a pool on three outcomes, closing at `POOL_CUTOFF`, paying the pool
+        /// creation fee. Everyone is given enough to stake on it.
+        fn create_sample_pool(betting: &mut Betting) -> u32 {
+            let accounts = default_accounts();
+            for account in [accounts.bob, accounts.django, accounts.eve] {
+                ink::env::test::set_account_balance::<Environment>(account, 1_000);
+            }
+            let (_, _, fee, _) = betting.get_pool_config();
+            set_next_caller(accounts.alice);
+            let pool = ink::env::pay_with_call!(
+                betting.create_pool(
+                    "Which of red, blue or green wins the tournament?".into(),
+                    3,
+                    "2023-12-21T00:00:00Z".into(),
+                    None
+                ),
+                fee
+            )
+            .unwrap();
+            assert_solvent(betting);
+            pool
+        }
+
+        /// `backer` stakes `amount` on `outcome` of pool `n`
+        fn stake(betting: &mut Betting, n: u32, backer: AccountId, outcome: u8, amount: Balance) {
+            set_next_caller(backer);
+            ink::env::pay_with_call!(betting.stake_on_outcome(n, outcome, amount), amount).unwrap();
+            assert_solvent(betting);
+        }
+
+        #[ink::test]
+        fn pool_is_split_pro_rata_with_the_rake_and_dust() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, django, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+                accounts.frank,
+            );
+            let fee = 10;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_appeal_config(100, fee).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.set_pool_config(10_001, frank, 0, 0),
+                Err(Error::InvalidBasisPoints)
+            );
+            betting.set_pool_config(1_000, frank, fee, 1_000).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_pool_config(), (1_000, frank, fee, 1_000));
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_solvent(&betting);
+
+            set_next_caller(alice);
+            assert_eq!(
+                betting.create_pool("?".into(), 1, "2023-12-21T00:00:00Z".into(), None),
+                Err(Error::InvalidNumberOfOutcomes)
+            );
+            let pool = create_sample_pool(&mut betting);
+            assert_eq!(betting.get_number_of_pools(), 1);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.stake_on_outcome(pool, 3, 10), 10),
+                Err(Error::OutcomeDoesNotExist)
+            );
+            assert_eq!(
+                ink::env::pay_with_call!(betting.stake_on_outcome(pool, 0, 10), 9),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            // staking nothing does not make Charlie a backer, so Charlie can still rule on the pool
+            set_next_caller(charlie);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.stake_on_outcome(pool, 0, 0), 0),
+                Err(Error::InvalidStake)
+            );
+            assert_eq!(
+                betting.get_backer_stakes(pool, charlie),
+                Vec::<Balance>::new()
+            );
+            set_next_caller(alice);
+            stake(&mut betting, pool, alice, 0, 10);
+            stake(&mut betting, pool, bob, 0, 20);
+            stake(&mut betting, pool, django, 1, 71);
+            assert_eq!(betting.get_pool_stakes(pool), Ok(vec![30, 71, 0]));
+            assert_eq!(betting.get_backer_stakes(pool, alice), vec![10, 0, 0]);
+
+            // the pool is resolved once it closes, and nobody can stake after that
+            set_next_caller(alice);
+            assert_eq!(
+                betting.request_pool_resolution(pool),
+                Err(Error::PoolStillOpen)
+            );
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.stake_on_outcome(pool, 0, 10), 10),
+                Err(Error::PoolClosed)
+            );
+            set_next_caller(charlie);
+            assert_eq!(betting.request_pool_resolution(pool), Err(Error::NotBacker));
+            set_next_caller(alice);
+            assert_eq!(betting.request_pool_resolution(pool), Ok(vec![charlie]));
+            assert_solvent(&betting);
+            assert_eq!(betting.get_open_cases(charlie), 1);
+            set_next_caller(charlie);
+            assert_eq!(
+                betting.submit_pool_ruling(pool, PoolOutcome::Winner(3)),
+                Err(Error::OutcomeDoesNotExist)
+            );
+            betting
+                .submit_pool_ruling(pool, PoolOutcome::Winner(0))
+                .unwrap();
+            assert_eq!(betting.get_pool_state(pool), Ok(PoolState::Resolved));
+            assert_eq!(
+                betting.get_pool_outcome(pool),
+                Ok(Some(PoolOutcome::Winner(0)))
+            );
+            set_next_caller(alice);
+            assert_eq!(
+                betting.claim_pool_payout(pool),
+                Err(Error::AppealWindowOpen)
+            );
 
-        #[ink(message)]
-        pub fn is_registered_as_reviewer(&self) -> Result<bool, ()> {
-            Ok(self.reviewers.contains(self.env().caller()))
-        }
+            // 101 less the 10% rake is split 1:2 between Alice and Bob, rounding down
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF + 101);
+            set_next_caller(django);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(0));
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(30));
+            assert_solvent(&betting);
+            assert_eq!(betting.claim_pool_payout(pool), Err(Error::NotBacker));
+            assert_eq!(betting.get_pool_state(pool), Ok(PoolState::PayingOut));
+            // the treasury keeps half of the creation fee, and the rest goes to reviewers
+            assert_eq!(betting.get_treasury_balance(), 5 + fee / 2);
+            assert_eq!(betting.claimable_of(frank), 0);
 
-        #[ink(message)]
-        pub fn is_final_decision_maker(&self) -> Result<bool, ()> {
-            Ok(self.final_decision_maker == self.env().caller())
+            // the last winner to claim leaves the dust to the dust recipient
+            set_next_caller(bob);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(60));
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(frank), 1);
+            assert_eq!(betting.claimable_of(alice), 30);
+            assert_eq!(betting.get_reviewer_stats(charlie).rulings_upheld, 1);
+            assert_eq!(betting.get_open_cases(charlie), 0);
+            assert_solvent(&betting);
+            // the reviewer reward pool's half of the creation fee is owed to reviewers
+            assert_eq!(
+                betting.solvency_report().liabilities,
+                91 + fee + 5 + fee / 2
+            );
         }
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+        #[ink::test]
+        fn pool_is_refunded_when_nobody_backed_the_winner() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
+            let fee = 10;
 
-        fn default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
-            ink::env::test::default_accounts::<Environment>()
-        }
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_appeal_config(0, fee).unwrap();
+            assert_solvent(&betting);
+            betting.set_pool_config(1_000, frank, fee, 1_000).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_solvent(&betting);
 
-        fn set_next_caller(caller: AccountId) {
-            ink::env::test::set_caller::<Environment>(caller)
-        }
+            let pool = create_sample_pool(&mut betting);
+            stake(&mut betting, pool, alice, 0, 10);
+            stake(&mut betting, pool, alice, 1, 15);
+            stake(&mut betting, pool, bob, 1, 33);
 
-        fn create_sample_bet(
-            betting: &mut Betting,
-            bob: Option<AccountId>,
-            amount_to_wager: Balance,
-            fee: Balance,
-        ) -> u32 {
-            let event_concludes_by = String::from("2023-12-21T00:00:00Z");
-            let criteria_for_winning: String =
-                "Red wins game against blue on December 21st, 2023.".into();
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF);
+            set_next_caller(bob);
+            betting.request_pool_resolution(pool).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            betting
+                .submit_pool_ruling(pool, PoolOutcome::Winner(2))
+                .unwrap();
 
-            ink::env::pay_with_call!(
-                betting.create_bet(
-                    amount_to_wager,
-                    bob,
-                    criteria_for_winning.clone(),
-                    event_concludes_by.clone()
-                ),
-                amount_to_wager + 2 * fee
-            )
-            .unwrap()
-            .unwrap()
+            // everybody gets their own stakes back, and nothing is raked
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF + 1);
+            set_next_caller(alice);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(25));
+            assert_solvent(&betting);
+            set_next_caller(bob);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(33));
+            assert_solvent(&betting);
+            assert_eq!(betting.get_treasury_balance(), fee / 2);
+            assert_eq!(betting.claimable_of(frank), 0);
+            assert_solvent(&betting);
         }
 
-        #[cfg(any(feature = "runtime-benchmarks", feature = "std"))]
         #[ink::test]
-        fn test_helper_function() {
-            let alice = default_accounts().alice;
+        fn pool_ruling_is_appealed_and_overturned() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, eve, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.eve,
+                accounts.frank,
+            );
+            let fee = 10;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_solvent(&betting);
+            let pool = create_sample_pool(&mut betting);
+            stake(&mut betting, pool, alice, 0, 40);
+            stake(&mut betting, pool, bob, 1, 60);
+
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF);
             set_next_caller(alice);
-            let betting = Betting::new(alice, 1);
+            betting.request_pool_resolution(pool).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            betting
+                .submit_pool_ruling(pool, PoolOutcome::Winner(1))
+                .unwrap();
 
-            let dt1 = String::from("2022-01-01T12:34:56Z");
-            let dt2 = String::from("2023-12-22T00:00:00Z");
+            // only backers who lost the ruling can appeal it
+            set_next_caller(eve);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_pool_ruling(pool), fee),
+                Err(Error::NotBacker)
+            );
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.appeal_pool_ruling(pool), fee),
+                Err(Error::NotLosingBettor)
+            );
+            set_next_caller(alice);
+            ink::env::pay_with_call!(betting.appeal_pool_ruling(pool), fee).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_pool_state(pool), Ok(PoolState::RulingAppealed));
+            assert_eq!(
+                betting.claim_pool_payout(pool),
+                Err(Error::InvalidStateForCallingFunction)
+            );
+            assert_solvent(&betting);
 
-            // timestamp requires the `Z` at the end to specify UTC timezone
+            // Frank overturns the ruling: Alice gets her bond back plus half of Charlie's bond
+            set_next_caller(frank);
+            betting
+                .submit_final_pool_outcome(pool, PoolOutcome::Winner(0))
+                .unwrap();
+            assert_eq!(betting.claimable_of(alice), fee + fee / 2);
+            assert_eq!(betting.get_reviewer_stats(charlie).rulings_overturned, 1);
+            assert_eq!(betting.get_open_cases(charlie), 0);
             assert_eq!(
-                betting.has_past_datetime("2023-12-22T00:00:00".into()),
-                Err(Error::NotDatetimeString)
+                betting.get_pool_outcome(pool),
+                Ok(Some(PoolOutcome::Winner(0)))
+            );
+
+            // the final ruling cannot be appealed, so Alice can claim straight away
+            set_next_caller(alice);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(100));
+            assert_solvent(&betting);
+            set_next_caller(bob);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(0));
+            assert_solvent(&betting);
+        }
+
+        #[ink::test]
+        fn pool_reviewers_exclude_backers_and_overdue_pools_escalate() {
+            let accounts = default_accounts();
+            let (alice, charlie, django, eve, frank) = (
+                accounts.alice,
+                accounts.charlie,
+                accounts.django,
+                accounts.eve,
+                accounts.frank,
             );
+            let fee = 10;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_dispute_config(fee, Balance::MAX, 3).unwrap();
+            assert_solvent(&betting);
+            betting.set_ruling_deadline_config(100, 0).unwrap();
+            assert_solvent(&betting);
+            for reviewer in [alice, charlie, django] {
+                ink::env::test::set_account_balance::<Environment>(reviewer, 1_000);
+                set_next_caller(reviewer);
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            }
+            let pool = create_sample_pool(&mut betting);
+            stake(&mut betting, pool, charlie, 0, 10);
+            stake(&mut betting, pool, eve, 1, 10);
+
+            // Alice created the pool and Charlie backs it, so only Django can rule on it
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF);
+            set_next_caller(eve);
             assert_eq!(
-                betting.convert_datetime_to_ms(dt1.clone()),
-                Ok(1_641_040_496_000)
+                ink::env::pay_with_call!(betting.request_pool_resolution(pool), fee),
+                Ok(vec![django])
             );
+            assert_solvent(&betting);
 
+            // Django misses the deadline, so Frank decides and the dispute fee is refunded
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF + 101);
+            assert_eq!(betting.reassign_overdue_pool_reviewers(pool), Ok(vec![]));
+            assert_solvent(&betting);
             assert_eq!(
-                betting.convert_datetime_to_ms(dt2.clone()),
-                Ok(1_703_203_200_000)
+                betting.get_pool_state(pool),
+                Ok(PoolState::DisputeEscalated)
             );
+            assert_eq!(betting.get_reviewer_stats(django).missed_deadlines, 1);
+            set_next_caller(frank);
+            betting
+                .submit_final_pool_outcome(pool, PoolOutcome::Undecideable)
+                .unwrap();
+            assert_eq!(betting.claimable_of(eve), fee);
+            set_next_caller(eve);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(10));
+            assert_solvent(&betting);
         }
 
         #[ink::test]
-        fn register_alice() {
-            let alice = default_accounts().alice;
-            set_next_caller(alice);
-            let mut betting = Betting::new(alice, 1);
+        fn pool_transition_table_only_pays_out_resolved_pools() {
+            use PoolState::*;
+            let states = [
+                Open,
+                ReviewerAssigned,
+                DisputeEscalated,
+                Resolved,
+                RulingAppealed,
+                PayingOut,
+            ];
 
-            // not registered yet
+            for from in states {
+                assert_eq!(
+                    Betting::is_valid_pool_transition(from, PayingOut),
+                    from == Resolved,
+                    "{:?}",
+                    from
+                );
+                for to in states {
+                    if from == PayingOut || to == Open {
+                        assert!(
+                            !Betting::is_valid_pool_transition(from, to),
+                            "{:?} -> {:?}",
+                            from,
+                            to
+                        );
+                    }
+                }
+            }
+            assert!(Betting::is_valid_pool_transition(Open, DisputeEscalated));
+            assert!(Betting::is_valid_pool_transition(Open, Resolved));
+            assert!(!Betting::is_valid_pool_transition(
+                DisputeEscalated,
+                RulingAppealed
+            ));
+            assert!(Betting::is_valid_pool_transition(RulingAppealed, Resolved));
+
+            let mut pool = Pool {
+                creator: AccountId::from([0x01; 32]),
+                criteria_for_winning: String::new(),
+                betting_closes_at: 0,
+                resolution_deadline: 0,
+                token: None,
+                rake_basis_points: 0,
+                dust_recipient: AccountId::from([0x01; 32]),
+                stakes_per_outcome: vec![0, 0],
+                total_staked: 0,
+                reviewers: Vec::new(),
+                reviewer_votes: Vec::new(),
+                ruling_deadline: None,
+                overdue_reviewers: Vec::new(),
+                reassignments: 0,
+                resolution_requested_by: None,
+                dispute_fee_paid: 0,
+                outcome_ruled_by_reviewers: None,
+                reviewer_ruled_at: None,
+                appellant: None,
+                appeal_bond_paid: 0,
+                outcome_decided_by_final_decision_maker: None,
+                paying_stake: 0,
+                payout: 0,
+                unclaimed_stake: 0,
+                unpaid_payout: 0,
+                state: Open,
+            };
             assert_eq!(
-                betting.is_registered_as_reviewer(),
-                Ok(false),
-                "Alice was already registered"
+                Betting::set_pool_state(&mut pool, RulingAppealed),
+                Err(Error::InvalidStateForCallingFunction)
             );
+            assert_eq!(pool.state, Open);
+            Betting::set_pool_state(&mut pool, Resolved).unwrap();
+            assert_eq!(pool.state, Resolved);
+        }
+
+        #[ink::test]
+        fn create_pool_charges_the_fee_and_bounds_the_criteria() {
+            let accounts = default_accounts();
+            let (alice, frank) = (accounts.alice, accounts.frank);
+            let fee = 10;
 
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
             assert_eq!(
-                betting.number_of_reviewers, 0,
-                "Smart contract was initialized with at least 1 reviewer."
+                betting.get_pool_config(),
+                (0, frank, fee, DEFAULT_POOL_RESOLUTION_PERIOD)
             );
+            let create = |betting: &mut Betting, criteria: String, amount: Balance| {
+                set_next_caller(alice);
+                ink::env::pay_with_call!(
+                    betting.create_pool(criteria, 2, "2023-12-21T00:00:00Z".into(), None),
+                    amount
+                )
+            };
 
-            // but is final decision maker
             assert_eq!(
-                betting.is_final_decision_maker(),
-                Ok(true),
-                "Alice is not the final decision maker"
+                create(&mut betting, "Heads or tails?".into(), fee - 1),
+                Err(Error::InssufficientAmountOfTokensSent)
+            );
+            assert_eq!(
+                create(&mut betting, "?".repeat(MAX_CRITERIA_LENGTH + 1), fee),
+                Err(Error::CriteriaTooLong)
             );
+            assert_eq!(betting.get_number_of_pools(), 0);
 
-            let register = betting.register_as_reviewer();
-            assert!(register.is_ok(), "Unable to register Alice.");
+            // the fee is split like a bet creation fee, and the overpayment is credited back
+            assert_eq!(
+                create(&mut betting, "?".repeat(MAX_CRITERIA_LENGTH), fee + 5),
+                Ok(0)
+            );
+            assert_solvent(&betting);
+            assert_eq!(create(&mut betting, "Heads or tails?".into(), fee), Ok(1));
+            assert_solvent(&betting);
+            assert_eq!(betting.get_number_of_pools(), 2);
+            assert_eq!(betting.get_pool_state(1), Ok(PoolState::Open));
+            assert_eq!(betting.get_pool_state(2), Err(Error::PoolDoesNotExist));
+            assert_eq!(betting.claimable_of(alice), 5);
+            assert_eq!(betting.get_treasury_balance(), fee);
+            assert_eq!(betting.get_reviewer_reward_pool(), fee);
+        }
 
-            // registered
+        #[ink::test]
+        fn pool_without_eligible_reviewers_escalates() {
+            let accounts = default_accounts();
+            let (alice, bob, eve, frank) =
+                (accounts.alice, accounts.bob, accounts.eve, accounts.frank);
+            let fee = 10;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_dispute_config(fee, Balance::MAX, 3).unwrap();
+            assert_solvent(&betting);
+            // Alice created the pool, so she cannot rule on it
+            set_next_caller(alice);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_solvent(&betting);
+            let pool = create_sample_pool(&mut betting);
+            stake(&mut betting, pool, bob, 0, 30);
+            stake(&mut betting, pool, eve, 1, 20);
+
+            // with nobody to rule, the dispute fee is escrowed and Frank decides instead
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF);
+            set_next_caller(eve);
             assert_eq!(
-                betting.is_registered_as_reviewer(),
-                Ok(true),
-                "Alice is not registered"
+                ink::env::pay_with_call!(betting.request_pool_resolution(pool), fee),
+                Ok(vec![])
+            );
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.get_pool_state(pool),
+                Ok(PoolState::DisputeEscalated)
             );
+            assert_eq!(betting.get_pool_reviewers(pool), Ok(vec![]));
+            assert_eq!(betting.get_open_cases(alice), 0);
 
-            assert_eq!(betting.number_of_reviewers, 1, "Wrong number of reviewers.");
+            set_next_caller(frank);
+            betting
+                .submit_final_pool_outcome(pool, PoolOutcome::Winner(1))
+                .unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(eve), fee);
+            set_next_caller(eve);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(50));
+            assert_solvent(&betting);
         }
 
         #[ink::test]
-        fn create_bet() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-            let event_concludes_by = String::from("2023-12-21T00:00:00Z");
+        fn unresolved_pool_expires_and_refunds_every_stake() {
+            let accounts = default_accounts();
+            let (alice, bob, eve, frank) =
+                (accounts.alice, accounts.bob, accounts.eve, accounts.frank);
+            let fee = 10;
 
+            use_separate_contract_account();
+            let mut betting = Betting::new(frank, fee);
+            set_next_caller(frank);
+            betting.set_pool_config(1_000, frank, fee, 1_000).unwrap();
+            assert_solvent(&betting);
+            let pool = create_sample_pool(&mut betting);
+            stake(&mut betting, pool, bob, 0, 30);
+            stake(&mut betting, pool, eve, 1, 20);
+
+            // nobody asks for a ruling, so anyone can expire the pool after the deadline
             set_next_caller(alice);
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF + 1_000);
+            assert_eq!(betting.expire_pool(pool), Err(Error::PoolNotExpired));
+            assert_eq!(betting.expire_pool(pool + 1), Err(Error::PoolDoesNotExist));
+            ink::env::test::set_block_timestamp::<Environment>(POOL_CUTOFF + 1_001);
+            betting.expire_pool(pool).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_pool_state(pool), Ok(PoolState::Resolved));
+            assert_eq!(betting.get_pool_outcome(pool), Ok(None));
+            assert_eq!(
+                betting.expire_pool(pool),
+                Err(Error::InvalidStateForCallingFunction)
+            );
 
-            let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
-            assert!(betting.balance().unwrap() == 1_000_000); // alice has 1 million to begin with
+            // every stake comes back without a rake
+            set_next_caller(bob);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(30));
+            assert_solvent(&betting);
+            set_next_caller(eve);
+            assert_eq!(betting.claim_pool_payout(pool), Ok(20));
+            assert_solvent(&betting);
+            assert_eq!(betting.get_treasury_balance(), fee / 2);
+            assert_eq!(betting.claimable_of(frank), 0);
+        }
 
-            let criteria_for_winning: String =
-                "Red wins game against blue on December 21st, 2023.".into();
+        #[ink::test]
+        fn pro_rata_is_exact_for_the_largest_balances() {
+            assert_eq!(Betting::pro_rata(1, 91, 30), 3);
+            assert_eq!(Betting::pro_rata(20, 91, 30), 60);
+            assert_eq!(
+                Betting::pro_rata(Balance::MAX - 1, Balance::MAX - 2, Balance::MAX),
+                Balance::MAX - 3
+            );
+            assert_eq!(
+                Betting::pro_rata(Balance::MAX / 3, Balance::MAX, Balance::MAX / 2),
+                Balance::MAX / 3 * 2
+            );
+        }
 
-            let amount_to_wager = 100_000;
-            let amount_sent = amount_to_wager + 2 * fee;
-            let bet_number = ink::env::pay_with_call!(
-                betting.create_bet(
-                    amount_to_wager,
-                    Some(bob),
-                    criteria_for_winning.clone(),
-                    event_concludes_by.clone()
-                ),
-                amount_sent
-            )
-            .unwrap()
-            .unwrap();
-            assert_eq!(bet_number, 0);
+        #[ink::test]
+        fn bets_over_labelled_outcomes_reject_invalid_indices() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let amount_to_wager = 100;
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, 0);
+            let race = || -> Vec<String> {
+                vec!["Red".into(), "Blue".into(), "Green".into(), "Yellow".into()]
+            };
+            let create = |betting: &mut Betting, outcomes: Vec<String>, backed: Vec<u8>| {
+                set_next_caller(alice);
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        amount_to_wager,
+                        amount_to_wager,
+                        Some(bob),
+                        "Red wins the race on December 21st, 2023.".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        false,
+                        None,
+                        None,
+                        outcomes,
+                        backed
+                    ),
+                    amount_to_wager
+                )
+            };
+            assert_solvent(&betting);
 
-            // check using getter methods
             assert_eq!(
-                betting.get_bettor_account_id(bet_number, 1),
-                Ok(Some(alice))
+                create(&mut betting, vec!["Red".into()], vec![0]),
+                Err(Error::InvalidNumberOfOutcomes)
             );
-            assert_eq!(betting.get_bettor_account_id(bet_number, 2), Ok(Some(bob)));
             assert_eq!(
-                betting.get_criteria_for_winning(bet_number),
-                Ok(criteria_for_winning)
+                create(&mut betting, vec!["Red".into(), "x".repeat(65)], vec![0]),
+                Err(Error::OutcomeLabelTooLong)
             );
             assert_eq!(
-                betting.get_event_decided_by(bet_number),
-                Ok(event_concludes_by)
+                create(&mut betting, race(), vec![4]),
+                Err(Error::OutcomeDoesNotExist)
             );
             assert_eq!(
-                betting.get_event_decided_by_as_ms(bet_number),
-                Ok(1703116800000)
+                create(&mut betting, race(), vec![0, 0]),
+                Err(Error::InvalidBacking)
             );
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Created));
-            let amount_wagered = betting.get_amount_wagered(bet_number).unwrap();
             assert_eq!(
-                amount_wagered, amount_to_wager,
-                "Actual amount: {amount_wagered}"
+                create(&mut betting, race(), vec![0, 1, 2, 3]),
+                Err(Error::InvalidBacking)
+            );
+            assert_eq!(
+                create(&mut betting, Vec::new(), vec![0]),
+                Err(Error::InvalidBacking)
+            );
+
+            // Alice backs red and Bob backs blue and green, so nobody backs yellow
+            let bet_number = create(&mut betting, race(), vec![0]).unwrap().unwrap();
+            assert_eq!(betting.get_outcomes(bet_number), Ok(race()));
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, vec![0, 1]), 100),
+                Err(Error::InvalidBacking)
+            );
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, vec![1, 2]), 100).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.get_backed_outcomes(bet_number),
+                Ok((vec![0], vec![1, 2]))
+            );
+
+            // indices past the undecideable one are rejected rather than coerced
+            assert_eq!(
+                betting.submit_outcome(bet_number, 5),
+                Err(Error::OutcomeDoesNotExist)
+            );
+            betting.submit_outcome(bet_number, 2).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 2).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor2Wins));
+
+            // Bob backs everything Alice did not by default, and an unbacked outcome is a draw
+            let bet_number = create(&mut betting, race(), vec![0, 3]).unwrap().unwrap();
+            set_next_caller(bob);
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 100).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.get_backed_outcomes(bet_number),
+                Ok((vec![0, 3], vec![1, 2]))
+            );
+            betting.submit_outcome(bet_number, 3).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(alice);
+            betting.submit_outcome(bet_number, 3).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
+
+            // bets on which bettor wins only know outcomes 0 to 3
+            set_next_caller(alice);
+            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, 0);
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.accept_bet(bet_number, vec![0]), 100),
+                Err(Error::InvalidBacking)
+            );
+            ink::env::pay_with_call!(betting.accept_bet(bet_number, Vec::new()), 100).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(
+                betting.submit_outcome(bet_number, 4),
+                Err(Error::OutcomeDoesNotExist)
             );
+            assert_eq!(betting.submit_outcome(bet_number, 3), Ok(()));
+            assert_solvent(&betting);
+        }
+
+        /// How many bytes the contract's root storage cell takes. It has to fit in the 16 KiB
+        /// buffer storage values are encoded into.
+        fn root_cell_size(betting: &Betting) -> usize {
+            let mut cell = Vec::new();
+            ink::storage::traits::Storable::encode(betting, &mut cell);
+            cell.len()
         }
 
         #[ink::test]
-        fn create_and_accept_bet_when_bettor2_is_assgined() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-            let charlie = default_accounts().charlie;
+        fn outcome_labels_stay_out_of_the_root_storage_cell() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, 0);
+            let longest: Vec<String> = (0..MAX_OUTCOMES)
+                .map(|outcome| format!("{:064}", outcome))
+                .collect();
+            assert_eq!(longest[0].len(), MAX_OUTCOME_LABEL_LENGTH);
+            let create = |betting: &mut Betting, outcomes: Vec<String>| {
+                set_next_caller(alice);
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        1,
+                        1,
+                        Some(bob),
+                        "Which colour wins the race on December 21st, 2023?".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        false,
+                        None,
+                        None,
+                        outcomes,
+                        vec![0]
+                    ),
+                    1
+                )
+                .unwrap()
+                .unwrap()
+            };
+
+            // the longest labels take no more of the root cell than the shortest
+            let before = root_cell_size(&betting);
+            create(&mut betting, vec!["a".into(), "b".into()]);
+            let shortest_growth = root_cell_size(&betting) - before;
+            let before = root_cell_size(&betting);
+            let bet_number = create(&mut betting, longest.clone());
+            assert_eq!(root_cell_size(&betting) - before, shortest_growth);
+            assert_eq!(betting.get_outcomes(bet_number), Ok(longest.clone()));
 
+            // stored inline, eight of these bets alone would fill the buffer
+            for _ in 0..8 {
+                create(&mut betting, longest.clone());
+            }
+            assert!(root_cell_size(&betting) < 4 * 1024);
+            assert_solvent(&betting);
+
+            // bets filled from an offer share its labels rather than copying them
             set_next_caller(alice);
-            let amount_to_wager = 100;
-            let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            let offer = ink::env::pay_with_call!(
+                betting.post_offer(
+                    10,
+                    10,
+                    "Which colour wins the race on December 21st, 2023?".into(),
+                    "2023-12-21T00:00:00Z".into(),
+                    false,
+                    None,
+                    None,
+                    longest.clone(),
+                    vec![0]
+                ),
+                10
+            )
+            .unwrap();
+            set_next_caller(bob);
+            let filled =
+                ink::env::pay_with_call!(betting.fill_offer(offer, 5, Vec::new()), 5).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_outcomes(filled), Ok(longest));
+            assert_eq!(
+                betting.get_backed_outcomes(filled),
+                Ok((vec![0], (1..MAX_OUTCOMES).collect()))
+            );
+        }
 
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+        #[ink::test]
+        fn offers_fill_in_part_and_each_fill_settles_on_its_own() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, django) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+            );
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+            for account in [bob, charlie, django] {
+                ink::env::test::set_account_balance::<Environment>(account, 1_000);
+            }
+            let mut betting = Betting::new(alice, 0);
 
-            // Charlie should not be able to accept or reject the bet from Alice
+            // Charlie offers 300 against up to 100 from takers, at 3:1
             set_next_caller(charlie);
+            let post = |betting: &mut Betting, stake_charlie: Balance, stake_takers: Balance| {
+                ink::env::pay_with_call!(
+                    betting.post_offer(
+                        stake_charlie,
+                        stake_takers,
+                        "Red wins game against blue on December 21st, 2023.".into(),
+                        "2023-12-21T00:00:00Z".into(),
+                        false,
+                        None,
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    stake_charlie
+                )
+            };
+            assert_solvent(&betting);
+            assert_eq!(post(&mut betting, 300, 0), Err(Error::InvalidOfferSize));
+            let offer = post(&mut betting, 300, 100).unwrap();
+            let other_offer = post(&mut betting, 50, 50).unwrap();
+            assert_eq!(betting.get_number_of_offers(), 2);
+            assert_solvent(&betting);
+
+            // the maker cannot take their own offer, and nobody can take more than is left
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), 0),
-                Err(Error::InssufficientAmountOfTokensSent)
-            );
-            assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Err(Error::NotBettor2)
+                ink::env::pay_with_call!(betting.fill_offer(offer, 10, Vec::new()), 10),
+                Err(Error::CallerNotValidBettor)
             );
+            set_next_caller(bob);
             assert_eq!(
-                ink::env::pay_with_call!(betting.reject_bet(bet_number), 0),
-                Err(Error::NotBettor2)
+                ink::env::pay_with_call!(betting.fill_offer(offer, 101, Vec::new()), 101),
+                Err(Error::InvalidOfferSize)
             );
+            // nor a sliver of it: a fill takes at least 5% unless it takes the rest
             assert_eq!(
-                ink::env::pay_with_call!(betting.reject_bet(bet_number), amount_to_wager),
-                Err(Error::NotBettor2)
+                ink::env::pay_with_call!(betting.fill_offer(offer, 4, Vec::new()), 4),
+                Err(Error::FillTooSmall)
             );
 
-            // Bob should be able to accept if he sent sufficient coins
-            set_next_caller(bob);
+            // Bob takes 40 of it, matching 120 of Charlie's stake
+            let bobs_bet =
+                ink::env::pay_with_call!(betting.fill_offer(offer, 40, Vec::new()), 40).unwrap();
+            assert_eq!(betting.get_stakes(bobs_bet), Ok((120, 40)));
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), 0),
-                Err(Error::InssufficientAmountOfTokensSent)
+                betting.get_bet_state(bobs_bet),
+                Ok(BetState::BetAcceptedByBettor2)
             );
+            assert_eq!(betting.get_offer_remaining(offer), Ok((180, 60)));
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
+                betting.list_open_offers(0, 10),
+                vec![(offer, 180, 60), (other_offer, 50, 50)]
             );
-        }
-
-        #[ink::test]
-        fn create_and_reject_bet_when_bettor2_is_assgined() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-            let charlie = default_accounts().charlie;
-
-            set_next_caller(alice);
-            let amount_to_wager = 100;
-            let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
-
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+            assert_solvent(&betting);
 
-            // Charlie should not be able to accept or reject the bet from Alice
-            set_next_caller(charlie);
+            // Django takes the rest, and the offer is filled
+            set_next_caller(django);
+            let djangos_bet =
+                ink::env::pay_with_call!(betting.fill_offer(offer, 60, Vec::new()), 60).unwrap();
+            assert_eq!(betting.get_stakes(djangos_bet), Ok((180, 60)));
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), 0),
-                Err(Error::InssufficientAmountOfTokensSent)
+                betting.get_offer_fills(offer, 0, 10),
+                Ok(vec![bobs_bet, djangos_bet])
             );
+            assert_eq!(betting.get_offer_fills(offer, 0, 1), Ok(vec![bobs_bet]));
+            assert_eq!(betting.get_offer_fills(offer, 1, 10), Ok(vec![djangos_bet]));
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Err(Error::NotBettor2)
+                betting.get_offer_fills(offer + 2, 0, 10),
+                Err(Error::OfferDoesNotExist)
             );
+            assert_eq!(betting.get_offer_state(offer), Ok(OfferState::Filled));
+            assert_eq!(betting.list_open_offers(0, 10), vec![(other_offer, 50, 50)]);
             assert_eq!(
-                ink::env::pay_with_call!(betting.reject_bet(bet_number), 0),
-                Err(Error::NotBettor2)
+                ink::env::pay_with_call!(betting.fill_offer(offer, 1, Vec::new()), 1),
+                Err(Error::InvalidStateForCallingFunction)
             );
+
+            // each fill settles on its own: Bob wins his, Charlie wins Django's
+            let settle = |betting: &mut Betting, taker: AccountId, bet: u32, winner: u8| {
+                set_next_caller(taker);
+                betting.submit_outcome(bet, winner).unwrap();
+                set_next_caller(charlie);
+                betting.submit_outcome(bet, winner).unwrap();
+                betting.withdraw_winnings(bet).unwrap();
+            };
+            assert_solvent(&betting);
+            settle(&mut betting, bob, bobs_bet, 2);
+            settle(&mut betting, django, djangos_bet, 1);
+            assert_eq!(betting.claimable_of(bob), 160);
+            assert_eq!(betting.claimable_of(django), 0);
+            assert_eq!(betting.claimable_of(charlie), 240);
+            assert_solvent(&betting);
+
+            // cancelling gives back what no fill matched
+            set_next_caller(bob);
+            assert_eq!(betting.cancel_offer(other_offer), Err(Error::NotBettor1));
+            ink::env::pay_with_call!(betting.fill_offer(other_offer, 20, Vec::new()), 20).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            betting.cancel_offer(other_offer).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.claimable_of(charlie), 270);
+            assert_eq!(betting.get_offer_remaining(other_offer), Ok((0, 0)));
             assert_eq!(
-                ink::env::pay_with_call!(betting.reject_bet(bet_number), amount_to_wager),
-                Err(Error::NotBettor2)
+                betting.get_offer_state(other_offer),
+                Ok(OfferState::Cancelled)
             );
+            assert!(betting.list_open_offers(0, 10).is_empty());
+            assert_solvent(&betting);
 
-            // Bob should be able to reject even if he sent zero coin
-            set_next_caller(bob);
+            // the last fill can take less than the minimum when that is all that is left
+            assert_eq!(betting.get_offer_config(), 500);
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), 0),
-                Err(Error::InssufficientAmountOfTokensSent)
+                betting.set_offer_config(5_000),
+                Err(Error::NotFinalDecisionMaker)
             );
+            set_next_caller(alice);
             assert_eq!(
-                ink::env::pay_with_call!(betting.reject_bet(bet_number), 0),
-                Ok(true)
+                betting.set_offer_config(10_001),
+                Err(Error::InvalidBasisPoints)
+            );
+            betting.set_offer_config(5_000).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            let last_offer = post(&mut betting, 10, 10).unwrap();
+            set_next_caller(bob);
+            assert_eq!(
+                ink::env::pay_with_call!(betting.fill_offer(last_offer, 4, Vec::new()), 4),
+                Err(Error::FillTooSmall)
             );
+            ink::env::pay_with_call!(betting.fill_offer(last_offer, 6, Vec::new()), 6).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(django);
+            ink::env::pay_with_call!(betting.fill_offer(last_offer, 4, Vec::new()), 4).unwrap();
+            assert_solvent(&betting);
+            assert_eq!(betting.get_offer_state(last_offer), Ok(OfferState::Filled));
         }
 
         #[ink::test]
-        fn bettors_agree_on_outcome_alice_wins() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
+        fn appeal_bond_is_forfeited_when_the_appellant_is_no_better_off() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, frank) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.frank,
+            );
 
-            set_next_caller(alice);
+            set_next_caller(frank);
             let amount_to_wager = 100;
             let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
-
-            // alice creates bet
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
-
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+            let mut betting = Betting::new(frank, fee);
+            use_separate_contract_account();
+            set_next_caller(charlie);
+            ink::env::pay_with_call!(betting.register_as_reviewer(), fee).unwrap();
+            assert_solvent(&betting);
+            let bet_number = create_disputed_bet(&mut betting, amount_to_wager, fee);
+            set_next_caller(alice);
+            betting.assign_reviewer(bet_number).unwrap();
+            assert_solvent(&betting);
+            set_next_caller(charlie);
+            betting
+                .submit_reviewer_outcome(bet_number, BetOutcome::Draw)
+                .unwrap();
 
-            // bob accepts bet
+            // Bob appeals the draw, but Frank rules that Alice won
             set_next_caller(bob);
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
-            );
-
-            // Event ends: Alice wins!
-            set_next_caller(alice);
-            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
-            assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::Bettor1Voted)
+                ink::env::pay_with_call!(betting.appeal_ruling(bet_number), fee),
+                Ok(())
             );
-
-            set_next_caller(bob);
-            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor1Wins));
-
-            set_next_caller(alice);
-            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
-            // Cannot withdraw winnings a 2nd time
+            assert_solvent(&betting);
+            let treasury_balance = betting.get_treasury_balance();
+            let (alice_claimable, bob_claimable) =
+                (betting.claimable_of(alice), betting.claimable_of(bob));
+            set_next_caller(frank);
             assert_eq!(
-                betting.withdraw_winnings(bet_number),
-                Err(Error::AlreadyWithdrewWinnings)
+                betting.submit_final_outcome(bet_number, BetOutcome::Bettor1Wins),
+                Ok(())
             );
+            assert_solvent(&betting);
+            assert_eq!(betting.get_treasury_balance(), treasury_balance + fee);
+            // the ruling was still overturned, in Alice's favor
+            assert_eq!(betting.get_reviewer_bond(charlie), fee - fee / 2);
+            assert_eq!(betting.get_reviewer_stats(charlie).rulings_overturned, 1);
+            assert_eq!(betting.claimable_of(alice), alice_claimable + fee / 2);
+            assert_eq!(betting.claimable_of(bob), bob_claimable);
+            // and Charlie is too short of the reviewer bond to be assigned new cases
+            assert_eq!(betting.get_number_of_reviewers(), 0);
+            assert_solvent(&betting);
         }
 
         #[ink::test]
-        fn bettors_agree_on_outcome_bob_wins() {
+        fn copied_commitment_cannot_be_revealed() {
             let alice = default_accounts().alice;
             let bob = default_accounts().bob;
 
@@ -801,194 +8497,355 @@ mod betting {
             let amount_to_wager = 100;
             let fee: Balance = 10;
             let mut betting = Betting::new(alice, fee);
+            let bet_number = create_commit_reveal_bet(&mut betting, amount_to_wager, fee);
 
-            // alice creates bet
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
-
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+            set_next_caller(alice);
+            let commitment = betting.get_outcome_commitment(bet_number, alice, 1, [1; 32]);
+            betting.commit_outcome(bet_number, commitment).unwrap();
 
-            // bob accepts bet
+            // Bob copies Alice's commitment and waits for her reveal to learn the salt
+            set_next_caller(bob);
+            betting.commit_outcome(bet_number, commitment).unwrap();
+            set_next_caller(alice);
+            betting.reveal_outcome(bet_number, 1, [1; 32]).unwrap();
             set_next_caller(bob);
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
+                betting.reveal_outcome(bet_number, 1, [1; 32]),
+                Err(Error::CommitmentMismatch)
             );
+            assert_solvent(&betting);
+        }
 
-            // Event ends: Bob wins!
-            set_next_caller(alice);
-            assert_eq!(betting.submit_outcome(bet_number, 2).is_ok(), true);
+        #[ink::test]
+        fn reviewer_overpayment_is_credited_back() {
+            let charlie = default_accounts().charlie;
+            let fee: Balance = 10;
+            let mut betting = Betting::new(default_accounts().alice, fee);
+            let contract = use_separate_contract_account();
+            ink::env::test::set_account_balance::<Environment>(contract, 0);
+
+            set_next_caller(charlie);
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::Bettor1Voted)
+                ink::env::pay_with_call!(betting.register_as_reviewer(), 3 * fee),
+                Ok(())
             );
+            assert_solvent(&betting);
+            assert_eq!(betting.get_reviewer_bond(charlie), fee);
+            assert_eq!(betting.claimable_of(charlie), 2 * fee);
+            assert_solvent(&betting);
 
-            set_next_caller(bob);
-            assert_eq!(betting.submit_outcome(bet_number, 2).is_ok(), true);
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Bettor2Wins));
-
-            // bob withdraws winnings
-            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
-            // Cannot withdraw winnings a 2nd time
+            // re-registering while unbonding only takes what the bond is short of
+            betting.unregister_as_reviewer().unwrap();
+            assert_solvent(&betting);
             assert_eq!(
-                betting.withdraw_winnings(bet_number),
-                Err(Error::AlreadyWithdrewWinnings)
+                ink::env::pay_with_call!(betting.register_as_reviewer(), fee),
+                Ok(())
             );
+            assert_solvent(&betting);
+            assert_eq!(betting.get_reviewer_bond(charlie), fee);
+            assert_eq!(betting.claimable_of(charlie), 3 * fee);
+            assert_solvent(&betting);
         }
 
         #[ink::test]
-        fn bettors_agree_on_outcome_draw() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-
-            set_next_caller(alice);
-            let amount_to_wager = 100;
-            let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
+        fn deployed_contracts_reach_tokens_with_cross_contract_calls() {
+            let betting = Betting::new(default_accounts().alice, 10);
+            assert_eq!(betting.token_transport, psp22::Transport::CrossContractCall);
+        }
 
-            // alice creates bet
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+        #[ink::test]
+        fn token_calls_decode_results_and_report_failures() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let token = AccountId::from([0x09; 32]);
+            let missing_token = AccountId::from([0x0a; 32]);
 
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+            let contract = use_separate_contract_account();
+            deploy_token(token, alice, 1_000);
+            with_token(token, alice, |t| t.transfer(contract, 10, Vec::new())).unwrap();
 
-            // bob accepts bet
-            set_next_caller(bob);
+            // the selectors reach the token's PSP22 messages and the results decode
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
+                psp22::balance_of(psp22::Transport::Mock, token, alice),
+                Some(990)
             );
-
-            // Event ends: Alice and Bob draws!
-            set_next_caller(alice);
-            assert_eq!(betting.submit_outcome(bet_number, 0).is_ok(), true);
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::Bettor1Voted)
+                psp22::transfer(psp22::Transport::Mock, token, bob, 4),
+                Ok(())
             );
-
-            set_next_caller(alice);
-            assert_eq!(betting.withdraw_winnings(bet_number), Ok(true));
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::Concluded));
-            // Cannot withdraw winnings a 2nd time
             assert_eq!(
-                betting.withdraw_winnings(bet_number),
-                Err(Error::AlreadyWithdrewWinnings)
+                psp22::balance_of(psp22::Transport::Mock, token, bob),
+                Some(4)
+            );
+            assert_eq!(
+                psp22::transfer_from(psp22::Transport::Mock, token, alice, bob, 1),
+                Err(Error::TokenTransferFailed)
+            );
+            with_token(token, alice, |t| t.approve(contract, 1)).unwrap();
+            assert_eq!(
+                psp22::transfer_from(psp22::Transport::Mock, token, alice, bob, 1),
+                Ok(())
             );
 
-            set_next_caller(bob);
-            assert_eq!(betting.submit_outcome(bet_number, 0).is_ok(), true);
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::BettorsDrew));
+            // an error from the token and a call that cannot reach it both fail the transfer
+            assert_eq!(
+                psp22::transfer(psp22::Transport::Mock, token, bob, 7),
+                Err(Error::TokenTransferFailed)
+            );
+            assert_eq!(
+                psp22::transfer(psp22::Transport::Mock, missing_token, bob, 1),
+                Err(Error::TokenTransferFailed)
+            );
+            assert_eq!(
+                psp22::balance_of(psp22::Transport::Mock, missing_token, alice),
+                None
+            );
         }
 
         #[ink::test]
-        fn bettors_agree_on_outcome_undecideable() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-
-            set_next_caller(alice);
-            let amount_to_wager = 100;
-            let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
-
-            // alice creates bet
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+        fn token_liabilities_are_covered_by_the_token_balance() {
+            let accounts = default_accounts();
+            let (alice, charlie) = (accounts.alice, accounts.charlie);
+            let (amount_to_wager, fee) = (100, 10);
+            let token = AccountId::from([0x09; 32]);
+            let report = |liabilities, treasury, free_balance| SolvencyReport {
+                liabilities,
+                treasury,
+                free_balance,
+                is_solvent: true,
+            };
 
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+            let contract = use_separate_contract_account();
+            deploy_token(token, alice, 1_000);
+            let mut betting = token_betting(alice, fee);
+            assert_eq!(betting.token_solvency_report(token), report(0, 0, 0));
 
-            // bob accepts bet
-            set_next_caller(bob);
+            // the wager is escrowed and the referrer's share of the fee is owed to them
+            with_token(token, alice, |t| t.approve(contract, amount_to_wager + fee)).unwrap();
+            create_token_bet(&mut betting, token, amount_to_wager, Some(charlie), 0)
+                .unwrap()
+                .unwrap();
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
+                betting.token_solvency_report(token),
+                report(amount_to_wager + 1, fee - 1, 0)
             );
 
-            // Event ends: bettors agree that conditions for winning turned out to be unclear!
-            set_next_caller(alice);
-            assert_eq!(betting.submit_outcome(bet_number, 3).is_ok(), true);
+            // claiming pays the liability off
+            set_next_caller(charlie);
+            betting.claim_tokens(token, 1).unwrap();
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::Bettor1Voted)
+                betting.token_solvency_report(token),
+                report(amount_to_wager, fee - 1, 0)
             );
 
-            set_next_caller(bob);
-            assert_eq!(betting.submit_outcome(bet_number, 3).is_ok(), true);
-            assert_eq!(betting.get_bet_state(bet_number), Ok(BetState::BettorsDrew));
+            // whatever is sent to the contract directly is free balance
+            with_token(token, alice, |t| t.transfer(contract, 5, Vec::new())).unwrap();
+            assert_eq!(
+                betting.token_solvency_report(token),
+                report(amount_to_wager, fee - 1, 5)
+            );
         }
 
         #[ink::test]
-        fn bettors_disagree_on_outcome_voted_for_self() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-
-            set_next_caller(alice);
-            let amount_to_wager = 100;
+        fn create_bet_rejects_zero_stakes() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
             let fee: Balance = 10;
             let mut betting = Betting::new(alice, fee);
+            use_separate_contract_account();
 
-            // alice creates bet
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
+            set_next_caller(alice);
+            for (stake_alice, stake_bob) in [(0, 100), (100, 0), (0, 0)] {
+                assert_eq!(
+                    ink::env::pay_with_call!(
+                        betting.create_bet(
+                            stake_alice,
+                            stake_bob,
+                            Some(bob),
+                            "Red wins".into(),
+                            "2023-12-21T00:00:00Z".into(),
+                            false,
+                            None,
+                            None,
+                            Vec::new(),
+                            Vec::new()
+                        ),
+                        stake_alice + fee
+                    ),
+                    Err(Error::InvalidStake)
+                );
+            }
+            assert_eq!(betting.latest_bet, 0);
+            assert_solvent(&betting);
+        }
 
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+        #[ink::test]
+        fn create_bet_bounds_the_criteria_and_event_date() {
+            let accounts = default_accounts();
+            let (alice, bob) = (accounts.alice, accounts.bob);
+            let fee: Balance = 10;
+            use_separate_contract_account();
+            let mut betting = Betting::new(alice, fee);
+            let create = |betting: &mut Betting, criteria: String, decided_by: String| {
+                set_next_caller(alice);
+                ink::env::pay_with_call!(
+                    betting.create_bet(
+                        100,
+                        100,
+                        Some(bob),
+                        criteria,
+                        decided_by,
+                        false,
+                        None,
+                        None,
+                        Vec::new(),
+                        Vec::new()
+                    ),
+                    100 + fee
+                )
+            };
 
-            // bob accepts bet
-            set_next_caller(bob);
             assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
+                create(
+                    &mut betting,
+                    "?".repeat(MAX_CRITERIA_LENGTH + 1),
+                    "2023-12-21T00:00:00Z".into()
+                ),
+                Err(Error::CriteriaTooLong)
             );
-
-            // Event ends: bettors disagree!
-            set_next_caller(alice);
-            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::Bettor1Voted)
+                create(
+                    &mut betting,
+                    "Red wins".into(),
+                    "2".repeat(MAX_CRITERIA_LENGTH + 1)
+                ),
+                Err(Error::CriteriaTooLong)
             );
-
-            set_next_caller(bob);
-            assert_eq!(betting.submit_outcome(bet_number, 2).is_ok(), true);
+            assert_eq!(betting.latest_bet, 0);
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::BettorsDisagree)
+                create(
+                    &mut betting,
+                    "?".repeat(MAX_CRITERIA_LENGTH),
+                    "2023-12-21T00:00:00Z".into()
+                ),
+                Ok(Some(0))
             );
+            assert_solvent(&betting);
         }
 
         #[ink::test]
-        fn bettors_disagree_on_outcome_alice_voted_for_self_bob_drew() {
-            let alice = default_accounts().alice;
-            let bob = default_accounts().bob;
-
+        fn offer_fills_stay_out_of_the_root_storage_cell() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie) = (accounts.alice, accounts.bob, accounts.charlie);
+            use_separate_contract_account();
+            for account in [bob, charlie] {
+                ink::env::test::set_account_balance::<Environment>(account, 1_000);
+            }
+            let mut betting = Betting::new(alice, 0);
             set_next_caller(alice);
-            let amount_to_wager = 100;
-            let fee: Balance = 10;
-            let mut betting = Betting::new(alice, fee);
+            betting.set_offer_config(0).unwrap();
 
-            // alice creates bet
-            let bet_number = create_sample_bet(&mut betting, Some(bob), amount_to_wager, fee);
-
-            assert_eq!(betting.get_amount_wagered(bet_number), Ok(amount_to_wager));
+            set_next_caller(charlie);
+            let offer = ink::env::pay_with_call!(
+                betting.post_offer(
+                    200,
+                    200,
+                    "Red wins game against blue on December 21st, 2023.".into(),
+                    "2023-12-21T00:00:00Z".into(),
+                    false,
+                    None,
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                200
+            )
+            .unwrap();
 
-            // bob accepts bet
+            // every fill is a bet of its own, and none of them grows the root cell
+            let before = root_cell_size(&betting);
             set_next_caller(bob);
-            assert_eq!(
-                ink::env::pay_with_call!(betting.accept_bet(bet_number), amount_to_wager),
-                Ok(true)
-            );
+            let fills: Vec<u32> = (0..200)
+                .map(|_| {
+                    ink::env::pay_with_call!(betting.fill_offer(offer, 1, Vec::new()), 1).unwrap()
+                })
+                .collect();
+            assert_eq!(root_cell_size(&betting), before);
+            assert_eq!(betting.get_offer_state(offer), Ok(OfferState::Filled));
+            assert_eq!(betting.get_offer_fills(offer, 0, 200), Ok(fills.clone()));
+            for bet in fills {
+                assert_eq!(betting.get_stakes(bet), Ok((1, 1)));
+            }
+            assert_solvent(&betting);
+        }
 
-            // Event ends: bettors disagree!
+        #[ink::test]
+        fn offer_takers_pay_the_acceptance_fee_quoted_when_it_was_posted() {
+            let accounts = default_accounts();
+            let (alice, bob, charlie, django) = (
+                accounts.alice,
+                accounts.bob,
+                accounts.charlie,
+                accounts.django,
+            );
+            use_separate_contract_account();
+            for account in [bob, charlie, django] {
+                ink::env::test::set_account_balance::<Environment>(account, 1_000);
+            }
+            let mut betting = Betting::new(alice, 0);
             set_next_caller(alice);
-            assert_eq!(betting.submit_outcome(bet_number, 1).is_ok(), true);
+            betting
+                .set_fee_model(FeeModel::Flat(30), FeePayer::Bettor2)
+                .unwrap();
+
+            set_next_caller(charlie);
+            let offer = ink::env::pay_with_call!(
+                betting.post_offer(
+                    100,
+                    100,
+                    "Red wins game against blue on December 21st, 2023.".into(),
+                    "2023-12-21T00:00:00Z".into(),
+                    false,
+                    None,
+                    None,
+                    Vec::new(),
+                    Vec::new()
+                ),
+                100
+            )
+            .unwrap();
+            assert_eq!(betting.get_offer_acceptance_fee(offer, 40), Ok(12));
+            assert_eq!(betting.get_offer_acceptance_fee(offer, 100), Ok(30));
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::Bettor1Voted)
+                betting.get_offer_acceptance_fee(offer, 101),
+                Err(Error::InvalidOfferSize)
             );
 
+            // raising the fee after the offer was posted does not change what its takers pay
+            set_next_caller(alice);
+            betting
+                .set_fee_model(FeeModel::Flat(500), FeePayer::Bettor2)
+                .unwrap();
+            assert_eq!(betting.get_offer_acceptance_fee(offer, 40), Ok(12));
+
+            // Bob pays a share of the quoted fee pro rata to the stake
             set_next_caller(bob);
-            assert_eq!(betting.submit_outcome(bet_number, 0).is_ok(), true);
             assert_eq!(
-                betting.get_bet_state(bet_number),
-                Ok(BetState::BettorsDisagree)
+                ink::env::pay_with_call!(betting.fill_offer(offer, 40, Vec::new()), 51),
+                Err(Error::InssufficientAmountOfTokensSent)
             );
+            let bobs_bet =
+                ink::env::pay_with_call!(betting.fill_offer(offer, 40, Vec::new()), 52).unwrap();
+            assert_eq!(betting.get_acceptance_fee(bobs_bet), Ok(12));
+            assert_solvent(&betting);
+
+            // and Django, who takes the rest, pays the rest of it
+            set_next_caller(django);
+            let djangos_bet =
+                ink::env::pay_with_call!(betting.fill_offer(offer, 60, Vec::new()), 78).unwrap();
+            assert_eq!(betting.get_acceptance_fee(djangos_bet), Ok(18));
+            assert_eq!(betting.get_offer_state(offer), Ok(OfferState::Filled));
+            assert_solvent(&betting);
         }
     }
 }
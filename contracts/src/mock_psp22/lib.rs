@@ -0,0 +1,164 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+pub use self::mock_psp22::{MockPsp22, MockPsp22Ref, PSP22Error};
+
+/// A bare-bones PSP22 token for trying out token wagers locally and on testnets.
+/// Anyone can mint, so it must never hold anything of value.
+#[ink::contract]
+mod mock_psp22 {
+    use ink::prelude::{string::String, vec::Vec};
+    use ink::storage::Mapping;
+
+    /// The errors defined by the PSP22 standard
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(::scale_info::TypeInfo))]
+    pub enum PSP22Error {
+        Custom(String),
+        InsufficientBalance,
+        InsufficientAllowance,
+        ZeroRecipientAddress,
+        ZeroSenderAddress,
+        SafeTransferCheckFailed(String),
+    }
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct MockPsp22 {
+        /// How many tokens exist
+        total_supply: Balance,
+        /// How many tokens each account holds
+        balances: Mapping<AccountId, Balance>,
+        /// How many tokens a spender may still move, by (owner, spender)
+        allowances: Mapping<(AccountId, AccountId), Balance>,
+    }
+
+    impl MockPsp22 {
+        /// Create the token, minting `initial_supply` to the caller
+        #[ink(constructor)]
+        pub fn new(initial_supply: Balance) -> Self {
+            let mut token = Self::default();
+            token.mint_to(Self::env().caller(), initial_supply);
+            token
+        }
+
+        /// Mint `value` new tokens to `to`
+        #[ink(message)]
+        pub fn mint(&mut self, to: AccountId, value: Balance) -> Result<(), PSP22Error> {
+            self.mint_to(to, value);
+            Ok(())
+        }
+
+        // The PSP22 messages, with the standard's selectors
+
+        #[ink(message, selector = 0x162df8c2)]
+        pub fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+
+        #[ink(message, selector = 0x6568382f)]
+        pub fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(owner).unwrap_or_default()
+        }
+
+        #[ink(message, selector = 0x4d47d921)]
+        pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get((owner, spender)).unwrap_or_default()
+        }
+
+        #[ink(message, selector = 0xdb20f9f5)]
+        pub fn transfer(
+            &mut self,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            self.move_tokens(self.env().caller(), to, value)
+        }
+
+        #[ink(message, selector = 0x54b3c76e)]
+        pub fn transfer_from(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+            _data: Vec<u8>,
+        ) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            let allowance = self.allowance(from, spender);
+            if allowance < value {
+                return Err(PSP22Error::InsufficientAllowance);
+            }
+            self.move_tokens(from, to, value)?;
+            self.allowances
+                .insert((from, spender), &(allowance - value));
+            Ok(())
+        }
+
+        #[ink(message, selector = 0xb20f1bbd)]
+        pub fn approve(&mut self, spender: AccountId, value: Balance) -> Result<(), PSP22Error> {
+            self.allowances
+                .insert((self.env().caller(), spender), &value);
+            Ok(())
+        }
+
+        fn mint_to(&mut self, to: AccountId, value: Balance) {
+            self.total_supply = self.total_supply.saturating_add(value);
+            let balance = self.balance_of(to).saturating_add(value);
+            self.balances.insert(to, &balance);
+        }
+
+        fn move_tokens(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<(), PSP22Error> {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(PSP22Error::InsufficientBalance);
+            }
+            self.balances.insert(from, &(from_balance - value));
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, &(to_balance + value));
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn transfers_need_a_balance() {
+            let accounts = ink::env::test::default_accounts::<Environment>();
+            let mut token = MockPsp22::new(100);
+            assert_eq!(token.total_supply(), 100);
+
+            token.transfer(accounts.bob, 60, Vec::new()).unwrap();
+            assert_eq!(
+                token.transfer(accounts.bob, 41, Vec::new()),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!(token.balance_of(accounts.alice), 40);
+            assert_eq!(token.balance_of(accounts.bob), 60);
+        }
+
+        #[ink::test]
+        fn transfer_from_spends_the_allowance() {
+            let accounts = ink::env::test::default_accounts::<Environment>();
+            let mut token = MockPsp22::new(100);
+            token.approve(accounts.bob, 30).unwrap();
+
+            ink::env::test::set_caller::<Environment>(accounts.bob);
+            token
+                .transfer_from(accounts.alice, accounts.charlie, 20, Vec::new())
+                .unwrap();
+            assert_eq!(
+                token.transfer_from(accounts.alice, accounts.charlie, 20, Vec::new()),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+            assert_eq!(token.allowance(accounts.alice, accounts.bob), 10);
+            assert_eq!(token.balance_of(accounts.charlie), 20);
+        }
+    }
+}